@@ -1,2 +1,3 @@
 mod network;
-pub mod training;
\ No newline at end of file
+pub mod training;
+pub mod planning;
\ No newline at end of file
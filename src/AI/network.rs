@@ -1,7 +1,10 @@
 pub mod network {
     use rand::Rng;
+    use serde::{Deserialize, Serialize};
+    use std::fs;
+    use std::path::Path;
 
-    #[derive(Copy, Clone)]
+    #[derive(Copy, Clone, Serialize, Deserialize)]
     pub enum ActivationFunction {
         ReLU,
         TANH,
@@ -12,12 +15,26 @@ pub mod network {
         pub activation_function: ActivationFunction,
     }
 
-    #[derive(Clone)]
+    #[derive(Clone, Serialize, Deserialize)]
     pub struct Network {
         layers: Vec<Layer>,
     }
 
     impl Network {
+        // Networks are stored as RON files, so they can be loaded by the game
+        pub fn load(path: &Path) -> Result<Self, String> {
+            let text = fs::read_to_string(path)
+                .map_err(|error| format!("cannot read network '{}': {}", path.display(), error))?;
+            ron::from_str(&text).map_err(|error| format!("invalid network '{}': {}", path.display(), error))
+        }
+        pub fn save(&self, path: &Path) -> Result<(), String> {
+            let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(|error| format!("cannot serialize network: {}", error))?;
+            fs::write(path, text).map_err(|error| format!("cannot write network '{}': {}", path.display(), error))
+        }
+        pub fn input_size(&self) -> usize {
+            self.layers.first().and_then(|layer| layer.neurons.first()).map_or(0, |neuron| neuron.weights.len())
+        }
         pub fn mutate(&self, other: &Self, mutation_rate: f32, mutation: f32) -> Self {
             let mut new_layers = Vec::new();
             for (layer1, layer2) in self.layers.iter().zip(other.layers.iter()) {
@@ -47,7 +64,7 @@ pub mod network {
         }
     }
 
-    #[derive(Clone)]
+    #[derive(Clone, Serialize, Deserialize)]
     struct Layer {
         neurons: Vec<Neuron>,
     }
@@ -77,7 +94,7 @@ pub mod network {
         }
     }
 
-    #[derive(Clone)]
+    #[derive(Clone, Serialize, Deserialize)]
     struct Neuron {
         bias: f32,
         weights: Vec<f32>,
@@ -0,0 +1,204 @@
+use std::collections::{HashMap, VecDeque};
+use std::mem::discriminant;
+use robotics_lib::world::tile::{Content, Tile};
+use crate::AI::training::BrainAction;
+use crate::player::messages::Coords;
+
+// Targets farther than this many steps are not searched, or an unreachable one would make
+// every search walk the whole known map
+pub const MAX_PLAN_STEPS: usize = 100;
+
+// What the robot is going for, only used to draw it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanKind {
+    GetContent,
+    PutContent,
+    Explore,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plan {
+    pub kind: PlanKind,
+    // The tile the robot wants to act on, or to see for Explore
+    pub target: Coords,
+    // From the robot to the tile next to the target, both included
+    pub path: Vec<Coords>,
+}
+
+// Shortest route on the known walkable tiles to a tile next to the target of the action.
+// It's an estimate: op_map only gives the next step and may weigh the tiles differently,
+// this is the route a plain BFS would take towards the same kind of target
+pub fn plan_route(known: &[Vec<Option<Tile>>], from: Coords, action: &BrainAction) -> Option<Plan> {
+    let (kind, is_target): (PlanKind, Box<dyn Fn(&Option<Tile>) -> bool>) = match action {
+        BrainAction::GetContent(content) => (PlanKind::GetContent, Box::new(move |tile| has_content(tile, content))),
+        BrainAction::PutContent(content, _) => (PlanKind::PutContent, Box::new(move |tile| has_content(tile, content))),
+        // The closest tile the robot has never seen
+        BrainAction::Explore() => (PlanKind::Explore, Box::new(|tile| tile.is_none())),
+    };
+
+    let size = known.len();
+    if from.0 >= size || from.1 >= size {
+        return None;
+    }
+    // Only the tiles reached so far, most searches stop long before the whole map
+    let mut previous: HashMap<Coords, Coords> = HashMap::new();
+    let mut queue = VecDeque::from([(from, 0)]);
+    previous.insert(from, from);
+
+    while let Some((current, steps)) = queue.pop_front() {
+        if steps == MAX_PLAN_STEPS {
+            continue;
+        }
+        for next in neighbours(current, size) {
+            if is_target(&known[next.0][next.1]) {
+                return Some(Plan { kind, target: next, path: rebuild_path(&previous, from, current) });
+            }
+            let walkable = known[next.0][next.1].as_ref().map_or(false, |tile| tile.tile_type.properties().walk());
+            if walkable && !previous.contains_key(&next) {
+                previous.insert(next, current);
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    None
+}
+
+fn has_content(tile: &Option<Tile>, content: &Content) -> bool {
+    tile.as_ref().map_or(false, |tile| discriminant(&tile.content) == discriminant(content))
+}
+
+fn neighbours((x, y): Coords, size: usize) -> impl Iterator<Item = Coords> {
+    let up = x.checked_sub(1).map(|x| (x, y));
+    let down = (x + 1 < size).then(|| (x + 1, y));
+    let left = y.checked_sub(1).map(|y| (x, y));
+    let right = (y + 1 < size).then(|| (x, y + 1));
+    [up, down, left, right].into_iter().flatten()
+}
+
+fn rebuild_path(previous: &HashMap<Coords, Coords>, from: Coords, to: Coords) -> Vec<Coords> {
+    let mut path = vec![to];
+    let mut current = to;
+    while current != from {
+        current = previous[&current];
+        path.push(current);
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robotics_lib::world::tile::TileType;
+
+    // '.' grass, '#' wall, 'g' garbage, 'b' bin and '?' a tile the robot has never seen.
+    // The maps are square, like the worlds
+    fn map(rows: &[&str]) -> Vec<Vec<Option<Tile>>> {
+        rows.iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| {
+                        let (tile_type, content) = match c {
+                            '.' => (TileType::Grass, Content::None),
+                            '#' => (TileType::Wall, Content::None),
+                            'g' => (TileType::Grass, Content::Garbage(1)),
+                            'b' => (TileType::Grass, Content::Bin(0..5)),
+                            _ => return None,
+                        };
+                        Some(Tile { tile_type, content, elevation: 0 })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn goes_next_to_the_target() {
+        let known = map(&[
+            "...",
+            "...",
+            "..g",
+        ]);
+        let plan = plan_route(&known, (0, 0), &BrainAction::GetContent(Content::Garbage(0))).unwrap();
+        assert_eq!(plan.kind, PlanKind::GetContent);
+        assert_eq!(plan.target, (2, 2));
+        assert_eq!(plan.path.first(), Some(&(0, 0)));
+        // Four steps away, the path stops on the tile before it
+        assert_eq!(plan.path.len(), 4);
+    }
+
+    #[test]
+    fn unreachable_target() {
+        let known = map(&[
+            "..#.",
+            "..#.",
+            "###.",
+            "...g",
+        ]);
+        assert_eq!(plan_route(&known, (0, 0), &BrainAction::GetContent(Content::Garbage(0))), None);
+        // Nothing like it is known at all
+        assert_eq!(plan_route(&known, (0, 0), &BrainAction::PutContent(Content::Bin(0..0), Content::Garbage(0))), None);
+    }
+
+    #[test]
+    fn explore_goes_to_the_closest_unknown_tile() {
+        let known = map(&[
+            "....",
+            "...?",
+            "????",
+            "????",
+        ]);
+        let plan = plan_route(&known, (0, 0), &BrainAction::Explore()).unwrap();
+        assert_eq!(plan.kind, PlanKind::Explore);
+        assert_eq!(plan.target, (2, 0));
+        assert_eq!(plan.path, vec![(0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn explore_with_everything_known() {
+        let known = map(&[
+            "..",
+            "..",
+        ]);
+        assert_eq!(plan_route(&known, (0, 0), &BrainAction::Explore()), None);
+    }
+
+    #[test]
+    fn too_far_away() {
+        // Open grass with the garbage `distance` tiles to the right of the robot
+        let garbage_at = |distance: usize| {
+            let size = MAX_PLAN_STEPS + 3;
+            let mut known = map(&vec![".".repeat(size).as_str(); size]);
+            known[0][distance] = Some(Tile { tile_type: TileType::Grass, content: Content::Garbage(1), elevation: 0 });
+            known
+        };
+        let action = BrainAction::GetContent(Content::Garbage(0));
+        assert!(plan_route(&garbage_at(MAX_PLAN_STEPS), (0, 0), &action).is_some());
+        assert_eq!(plan_route(&garbage_at(MAX_PLAN_STEPS + 2), (0, 0), &action), None);
+    }
+
+    #[test]
+    fn walls_block_the_path() {
+        let known = map(&[
+            "..#g",
+            ".##.",
+            "....",
+            "....",
+        ]);
+        let plan = plan_route(&known, (0, 0), &BrainAction::GetContent(Content::Garbage(0))).unwrap();
+        assert_eq!(plan.target, (0, 3));
+        // All around the walls, never through them
+        assert_eq!(plan.path, vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (2, 3), (1, 3)]);
+    }
+
+    #[test]
+    fn unknown_tiles_are_not_walked_on() {
+        let known = map(&[
+            ".?g",
+            "...",
+            "...",
+        ]);
+        let plan = plan_route(&known, (0, 0), &BrainAction::GetContent(Content::Garbage(0))).unwrap();
+        assert_eq!(plan.path, vec![(0, 0), (1, 0), (1, 1), (1, 2)]);
+    }
+}
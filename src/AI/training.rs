@@ -25,7 +25,8 @@ use std::time::Instant;
 use op_map::op_pathfinding::OpActionInput::Destroy;
 use rand::distributions::WeightedIndex;
 use worldgen_unwrap::public::WorldgeneratorUnwrap;
-use crate::WORLD_SIZE;
+use crate::world::resources::{ContentPreset, WorldSettings};
+use crate::config::BrainConfig;
 
 pub fn train(gen_size: u32) -> Network {
     println!("Started training");
@@ -37,10 +38,11 @@ pub fn train(gen_size: u32) -> Network {
             let mut handles = Vec::new();
 
             let seed: u64 = thread_rng().gen_range(421..=421);
+            let settings = WorldSettings::default();
 
             for brain in brains {
                 let h = s.spawn(move || {
-                    let mut w = generate_generator(seed);
+                    let mut w = generate_generator(seed, settings.world_size, settings.content_preset);
 
                     let my_robot = TrainingRobot::from_brain(brain);
                     let start = Instant::now(); // Start time
@@ -186,9 +188,24 @@ impl TrainingRobot {
     }
 }
 
+// How the brain picks the next BrainAction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+    // Hand written rules: get garbage until the backpack is full, then look for a bin
+    Heuristic,
+    // The network decides, its input are is_inv_full, is_there_garbage and is_there_bin
+    Network,
+}
+
+// While exploring, the robot looks SCAN_DISTANCE tiles away every SCAN_EVERY ticks
+const SCAN_EVERY: usize = 10;
+const SCAN_DISTANCE: usize = 8;
+const SCAN_MIN_ENERGY: usize = 200;
+
 #[derive(Clone)]
 pub struct Brain {
     pub network: Network,
+    pub policy: Policy,
 }
 
 impl Default for Brain {
@@ -201,6 +218,7 @@ impl Default for Brain {
                 LayerTopology { neurons: 4, activation_function: ActivationFunction::ReLU },
                 LayerTopology { neurons: 3, activation_function: ActivationFunction::TANH },
             ]),
+            policy: Policy::Heuristic,
         }
     }
 }
@@ -208,10 +226,36 @@ impl Default for Brain {
 impl Brain {
     pub fn from_network(network: Network) -> Self {
         Self {
-            network
+            network,
+            policy: Policy::Heuristic,
+        }
+    }
+    pub fn with_policy(network: Network, policy: Policy) -> Self {
+        Self {
+            network,
+            policy,
+        }
+    }
+    pub fn from_config(config: &BrainConfig) -> Result<Self, String> {
+        match config {
+            BrainConfig::Heuristic => Ok(Brain::default()),
+            BrainConfig::RandomNetwork => Ok(Brain::with_policy(Brain::default().network, Policy::Network)),
+            BrainConfig::Network(path) => {
+                let network = Network::load(path)?;
+                if network.input_size() != 3 {
+                    return Err(format!("network '{}' must have 3 inputs, it has {}", path.display(), network.input_size()));
+                }
+                Ok(Brain::with_policy(network, Policy::Network))
+            }
         }
     }
     pub fn think_action(&self, world: &World, robot: &impl Runnable) -> Option<OpActionOutput> {
+        let brain_action = self.decide(world, robot);
+        self.action_for(world, robot, brain_action)
+    }
+
+    // What the robot should do next, without working out how
+    pub fn decide(&self, world: &World, robot: &impl Runnable) -> BrainAction {
         let maybe_garbage_in_invetory = robot.get_backpack().get_contents().get(&Content::Garbage(0).to_default());
         let garbage_in_invetory = *maybe_garbage_in_invetory.unwrap_or(&0);
         let energy = robot.get_energy().get_energy_level();
@@ -238,27 +282,57 @@ impl Brain {
         // if is_inv_full > 0.1 && is_there_bin < 0.1 { random_index = 2 }
 
 
-        let random_index = if is_inv_full {
-            if is_there_bin {
-                1
-            } else {
-                2
+        let random_index = match self.policy {
+            Policy::Heuristic => {
+                if is_inv_full {
+                    if is_there_bin {
+                        1
+                    } else {
+                        2
+                    }
+                } else {
+                    if is_there_garbage {
+                        0
+                    } else {
+                        2
+                    }
+                }
             }
-        } else {
-            if is_there_garbage {
-                0
-            } else {
-                2
+            Policy::Network => {
+                let as_input = |b: bool| if b { 1.0 } else { 0.0 };
+                let res = self.network.propagate(vec![as_input(is_inv_full), as_input(is_there_garbage), as_input(is_there_bin)]);
+                // The output with the highest value wins
+                res.iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(b.1))
+                    .map_or(2, |(index, _)| index)
             }
         };
 
-        let brain_action = match random_index {
+        match random_index {
             0 => GetContent(Content::Garbage(1)),
             1 => PutContent(Content::Bin(0..0), Content::Garbage(1)),
             2 => Explore(),
             _ => panic!("Action not implemented"),
+        }
+    }
+
+    // Where to look with one_direction_view while exploring, turning clockwise every scan
+    pub fn scan_direction(&self, robot: &impl Runnable, tick: usize) -> Option<(Direction, usize)> {
+        if tick % SCAN_EVERY != 0 || robot.get_energy().get_energy_level() < SCAN_MIN_ENERGY {
+            return None;
+        }
+        let direction = match (tick / SCAN_EVERY) % 4 {
+            0 => Direction::Up,
+            1 => Direction::Right,
+            2 => Direction::Down,
+            _ => Direction::Left,
         };
+        Some((direction, SCAN_DISTANCE))
+    }
 
+    // The first step towards the BrainAction
+    pub fn action_for(&self, world: &World, robot: &impl Runnable, brain_action: BrainAction) -> Option<OpActionOutput> {
         let a: Option<OpActionOutput>;
         match brain_action {
             GetContent(c) => {
@@ -278,48 +352,75 @@ impl Brain {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum BrainAction {
     GetContent(Content),
     PutContent(Content, Content),
     Explore(),
 }
 
-pub fn generate_generator(seed: u64) -> impl Generator {
+pub fn generate_generator(seed: u64, size: usize, preset: ContentPreset) -> OxAgWorldGenerator {
     // println!("generated: ");
 
-    let generator: OxAgWorldGenerator = OxAgWorldGeneratorBuilder::new()
+    let builder = OxAgWorldGeneratorBuilder::new()
         .set_seed(seed)
-        .set_content_options_from_preset(OxAgContentPresets::Default)
-        .alter_content_option(Content::Garbage(0), OxAgContentOptions {
-            in_batches: true,
-            is_present: true,
-            max_radius: 5,
-            max_spawn_number: 500,
-            min_spawn_number: 50,
-            percentage: 0.1,
-            with_max_spawn_number: false,
-        }).unwrap()
-        .alter_content_option(Content::Bin(0..0), OxAgContentOptions {
-            in_batches: false,
-            is_present: true,
-            max_radius: 1,
-            max_spawn_number: 50,
-            min_spawn_number: 10,
-            percentage: 0.10,
-            with_max_spawn_number: false,
-        }).unwrap()
-        .alter_content_option(Content::Water(0), OxAgContentOptions {
-            in_batches: false,
-            is_present: true,
-            max_radius: 5,
-            max_spawn_number: 50,
-            min_spawn_number: 10,
-            percentage: 0.03,
-            with_max_spawn_number: false,
-        }).unwrap()
-        .set_size(WORLD_SIZE)
+        .set_content_options_from_preset(OxAgContentPresets::Default);
+
+    let builder = match preset {
+        ContentPreset::Default => builder,
+        ContentPreset::Garbage => builder
+            .alter_content_option(Content::Garbage(0), OxAgContentOptions {
+                in_batches: true,
+                is_present: true,
+                max_radius: 5,
+                max_spawn_number: 500,
+                min_spawn_number: 50,
+                percentage: 0.1,
+                with_max_spawn_number: false,
+            }).unwrap()
+            .alter_content_option(Content::Bin(0..0), OxAgContentOptions {
+                in_batches: false,
+                is_present: true,
+                max_radius: 1,
+                max_spawn_number: 50,
+                min_spawn_number: 10,
+                percentage: 0.10,
+                with_max_spawn_number: false,
+            }).unwrap()
+            .alter_content_option(Content::Water(0), OxAgContentOptions {
+                in_batches: false,
+                is_present: true,
+                max_radius: 5,
+                max_spawn_number: 50,
+                min_spawn_number: 10,
+                percentage: 0.03,
+                with_max_spawn_number: false,
+            }).unwrap(),
+        ContentPreset::Sparse => builder
+            .alter_content_option(Content::Garbage(0), OxAgContentOptions {
+                in_batches: false,
+                is_present: true,
+                max_radius: 2,
+                max_spawn_number: 50,
+                min_spawn_number: 5,
+                percentage: 0.01,
+                with_max_spawn_number: true,
+            }).unwrap()
+            .alter_content_option(Content::Bin(0..0), OxAgContentOptions {
+                in_batches: false,
+                is_present: true,
+                max_radius: 1,
+                max_spawn_number: 10,
+                min_spawn_number: 2,
+                percentage: 0.01,
+                with_max_spawn_number: true,
+            }).unwrap(),
+    };
+
+    let generator: OxAgWorldGenerator = builder
+        .set_size(size)
         .set_with_info(false)
         .build()
         .unwrap();
     return generator;
-}
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use crate::player::events::EventCategory;
 
 #[derive(Component)]
 pub struct UIDraw {}
@@ -36,6 +37,11 @@ pub struct ElevationText {}
 pub struct EnergyText {}
 
 
+// One line per robot, with its name, energy and score
+#[derive(Component)]
+pub struct RobotsText {}
+
+
 #[derive(Component)]
 pub struct TopLeftUI{}
 
@@ -52,3 +58,72 @@ pub struct PauseButton {}
 pub struct DoubleSpeedButton {}
 
 
+// Asks the followed robot to use Spotlight
+#[derive(Component)]
+pub struct SpotlightButton {}
+
+
+// Toggles the visit heatmap
+#[derive(Component)]
+pub struct HeatmapButton {}
+
+
+// Toggles the trail of the robots
+#[derive(Component)]
+pub struct TrailButton {}
+
+
+// Clears the heatmap and the trails
+#[derive(Component)]
+pub struct ResetVisitsButton {}
+
+
+
+
+#[derive(Component)]
+pub struct EventLogPanel {}
+
+
+#[derive(Component)]
+pub struct EventLogText {}
+
+
+// Queue depth of every robot, toggled with the debug_overlay key
+#[derive(Component)]
+pub struct DebugOverlayText {}
+
+
+// Height range of the elevation overlay
+#[derive(Component)]
+pub struct ElevationLegend {}
+
+
+#[derive(Component)]
+pub struct ElevationLegendTitle {}
+
+
+#[derive(Component)]
+pub struct ElevationLegendSwatch {}
+
+
+// Elevation next to the swatch number `step`, 0 is the lowest
+#[derive(Component)]
+pub struct ElevationLegendLabel {
+    pub step: usize,
+}
+
+
+#[derive(Component)]
+pub struct MinimapImage {}
+
+
+// Tooltip with what is known about the tile under the cursor
+#[derive(Component)]
+pub struct TileInspector {}
+
+
+// Shows or hides one category of events in the log
+#[derive(Component)]
+pub struct EventFilterButton {
+    pub category: EventCategory,
+}
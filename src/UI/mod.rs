@@ -2,24 +2,52 @@ use bevy::prelude::*;
 use crate::AppState;
 use systems::layout::*;
 use crate::UI::systems::interactions::*;
+use crate::UI::systems::event_log::*;
+use crate::UI::systems::debug_overlay::*;
+use crate::UI::systems::elevation_legend::*;
+use crate::UI::systems::minimap::*;
+use crate::UI::systems::inspector::*;
+use crate::UI::resources::EventLog;
+use crate::player::systems::forward_robot_events;
+use crate::main_menu::systems::interactions::interact_with_theme_button;
 
 mod components;
 mod styles;
 mod systems;
+mod resources;
 
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::Game), spawn_ui_menu)
-           .add_systems(OnExit(AppState::Game), despawn_ui_menu) // Might have to remove this for
+        app.init_resource::<EventLog>()
+           .add_systems(OnEnter(AppState::Game), (spawn_ui_menu, spawn_event_log, spawn_debug_overlay, spawn_elevation_legend, spawn_minimap, spawn_tile_inspector))
+           .add_systems(OnExit(AppState::Game), (despawn_ui_menu, despawn_event_log, despawn_debug_overlay, despawn_elevation_legend, despawn_minimap, despawn_tile_inspector)) // Might have to remove this for
                                                                  // pause state
            .add_systems(Update,
                 (
                     update_bottom_bar,
+                    update_robots_list,
                     interact_with_play_tick_button,
                     interact_with_pause_button,
                     interact_with_double_speed_button,
+                    interact_with_spotlight_button,
+                    (
+                        interact_with_heatmap_button,
+                        interact_with_trail_button,
+                        interact_with_reset_visits_button,
+                        interact_with_theme_button,
+                    ),
+                    record_robot_events.after(forward_robot_events),
+                    scroll_event_log,
+                    interact_with_event_filter_buttons,
+                    update_event_log_text.after(record_robot_events),
+                    toggle_debug_overlay,
+                    update_debug_overlay.after(toggle_debug_overlay),
+                    update_elevation_legend,
+                    update_minimap,
+                    interact_with_minimap,
+                    update_tile_inspector,
                 )
                 .run_if(in_state(AppState::Game))
             )
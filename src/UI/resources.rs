@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use crate::player::events::EventCategory;
+
+// Older entries are dropped once the log is this long
+pub const EVENT_LOG_CAPACITY: usize = 500;
+// Lines shown at once in the event log panel
+pub const EVENT_LOG_VISIBLE_LINES: usize = 14;
+
+pub struct LogEntry {
+    pub robot: usize,
+    pub tick: usize,
+    pub category: EventCategory,
+    pub text: String,
+}
+
+// Everything shown by the event log panel
+#[derive(Resource)]
+pub struct EventLog {
+    pub entries: VecDeque<LogEntry>,
+    // Categories shown in the panel
+    pub enabled: HashSet<EventCategory>,
+    // How many lines the panel is scrolled up from the newest entry
+    pub scroll: usize,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        EventLog {
+            entries: VecDeque::with_capacity(EVENT_LOG_CAPACITY),
+            // Time changes every tick, so it's hidden unless asked for
+            enabled: EventCategory::ALL
+                .iter()
+                .copied()
+                .filter(|category| *category != EventCategory::Time)
+                .collect(),
+            scroll: 0,
+        }
+    }
+}
+
+impl EventLog {
+    pub fn push(&mut self, entry: LogEntry) {
+        if self.entries.len() == EVENT_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn visible(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().filter(|entry| self.enabled.contains(&entry.category))
+    }
+}
+
+// The image drawn by the minimap, one pixel per tile
+#[derive(Resource)]
+pub struct Minimap {
+    pub image: Handle<Image>,
+}
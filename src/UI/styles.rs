@@ -21,3 +21,11 @@ pub fn get_button_text_style(asset_server: &Res<AssetServer>) -> TextStyle {
     }
 }
 
+pub fn get_small_text_style(asset_server: &Res<AssetServer>) -> TextStyle {
+    TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 14.0,
+        color: Color::WHITE,
+    }
+}
+
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use crate::UI::components::*;
+use crate::UI::styles::*;
+use crate::config::{KeyBindings, SimulationConfig};
+use crate::WorldRes;
+
+// Hidden until the debug_overlay key is pressed
+pub fn spawn_debug_overlay(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(4.0),
+                bottom: Val::Px(4.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        DebugOverlayText{},
+    ));
+}
+
+pub fn despawn_debug_overlay(
+    mut commands: Commands,
+    overlay_query: Query<Entity, With<DebugOverlayText>>,
+) {
+    if let Ok(overlay_entity) = overlay_query.get_single() {
+        commands.entity(overlay_entity).despawn_recursive();
+    }
+}
+
+pub fn toggle_debug_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    mut overlay_query: Query<&mut Visibility, With<DebugOverlayText>>,
+) {
+    if !key_bindings.debug_overlay.just_pressed(&keyboard_input) {
+        return;
+    }
+    if let Ok(mut visibility) = overlay_query.get_single_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+// Messages waiting for update_map, per robot
+pub fn update_debug_overlay(
+    world: Res<WorldRes>,
+    simulation_config: Res<SimulationConfig>,
+    asset_server: Res<AssetServer>,
+    mut overlay_query: Query<(&mut Text, &Visibility), With<DebugOverlayText>>,
+) {
+    let Ok((mut text, visibility)) = overlay_query.get_single_mut() else {
+        return;
+    };
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    text.sections = world
+        .robots
+        .iter()
+        .map(|robot| {
+            let depth = robot.queue_depth.get();
+            let waiting = if depth >= simulation_config.max_queued_messages { " (waiting)" } else { "" };
+            TextSection::new(
+                format!(
+                    "{}: queue {}/{}{}, most drained in a frame {}\n",
+                    robot.name, depth, simulation_config.max_queued_messages, waiting, robot.peak_drained
+                ),
+                get_small_text_style(&asset_server),
+            )
+        })
+        .collect();
+}
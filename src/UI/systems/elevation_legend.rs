@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+
+use crate::UI::components::*;
+use crate::UI::styles::*;
+use crate::config::RenderConfig;
+use crate::world::resources::{ElevationMode, ElevationOverlay};
+use crate::world::systems::elevation_color;
+use crate::WorldRes;
+
+// Colors shown in the legend, from the lowest to the highest tile
+const LEGEND_STEPS: usize = 5;
+
+// Hidden while the elevation overlay is off
+pub fn spawn_elevation_legend(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn(
+        (
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(4.0),
+                    top: Val::Percent(50.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ElevationLegend{},
+        )
+    )
+    .with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section("Elevation", get_small_text_style(&asset_server)),
+            ElevationLegendTitle{},
+        ));
+
+        // Swatches, the highest on top
+        for step in (0..LEGEND_STEPS).rev() {
+            parent.spawn(
+                (
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ElevationLegendSwatch{},
+                )
+            )
+            .with_children(|parent| {
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(14.0),
+                        height: Val::Px(14.0),
+                        ..default()
+                    },
+                    background_color: elevation_color(step, 0, LEGEND_STEPS - 1).into(),
+                    ..default()
+                });
+                parent.spawn((
+                    TextBundle::from_section("", get_small_text_style(&asset_server)),
+                    ElevationLegendLabel { step },
+                ));
+            });
+        }
+    });
+}
+
+pub fn despawn_elevation_legend(
+    mut commands: Commands,
+    legend_query: Query<Entity, With<ElevationLegend>>,
+) {
+    if let Ok(legend_entity) = legend_query.get_single() {
+        commands.entity(legend_entity).despawn_recursive();
+    }
+}
+
+pub fn update_elevation_legend(
+    world: Res<WorldRes>,
+    render_config: Res<RenderConfig>,
+    elevation_overlay: Res<ElevationOverlay>,
+    mut legend_query: Query<&mut Visibility, (With<ElevationLegend>, Without<ElevationLegendSwatch>)>,
+    mut swatch_query: Query<&mut Visibility, (With<ElevationLegendSwatch>, Without<ElevationLegend>)>,
+    mut title_query: Query<&mut Text, (With<ElevationLegendTitle>, Without<ElevationLegendLabel>)>,
+    mut label_query: Query<(&mut Text, &ElevationLegendLabel), Without<ElevationLegendTitle>>,
+) {
+    let mode = elevation_overlay.mode;
+    if let Ok(mut visibility) = legend_query.get_single_mut() {
+        *visibility = if mode == ElevationMode::Off { Visibility::Hidden } else { Visibility::Inherited };
+    }
+    if mode == ElevationMode::Off {
+        return;
+    }
+    // The swatches only make sense with the shading
+    for mut visibility in swatch_query.iter_mut() {
+        *visibility = if mode == ElevationMode::Shading { Visibility::Inherited } else { Visibility::Hidden };
+    }
+
+    let range = world.elevation_range();
+    if let Ok(mut title) = title_query.get_single_mut() {
+        title.sections[0].value = match (mode, range) {
+            (_, None) => "Elevation: nothing known yet".to_string(),
+            (ElevationMode::Contours, Some((min, max))) => {
+                format!("Elevation {} - {}\ncontour every {}", min, max, render_config.contour_interval)
+            }
+            (_, Some((min, max))) => format!("Elevation {} - {}", min, max),
+        };
+    }
+    if let Some((min, max)) = range {
+        for (mut text, label) in label_query.iter_mut() {
+            let elevation = min + (max - min) * label.step / (LEGEND_STEPS - 1);
+            text.sections[0].value = format!("{}", elevation);
+        }
+    }
+}
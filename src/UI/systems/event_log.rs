@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use bevy::input::mouse::MouseWheel;
+
+use crate::UI::components::*;
+use crate::UI::resources::*;
+use crate::UI::styles::*;
+use crate::player::events::{EventCategory, RobotEvent};
+use crate::WorldRes;
+
+const FILTER_ON_COLOR: Color = Color::rgb(0.45, 0.27, 0.0);
+const FILTER_OFF_COLOR: Color = Color::rgb(0.15, 0.1, 0.0);
+
+pub fn spawn_event_log(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    event_log: Res<EventLog>,
+) {
+    commands.spawn(
+        (
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(0.0),
+                    top: Val::Px(0.0),
+                    width: Val::Px(440.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            // Needed to know when the mouse is over the panel, to scroll it
+            Interaction::default(),
+            EventLogPanel{},
+        )
+    )
+    .with_children(|parent| {
+        // Filters
+        parent.spawn(
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(4.0),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            }
+        )
+        .with_children(|parent| {
+            for category in EventCategory::ALL {
+                let color = if event_log.enabled.contains(&category) { FILTER_ON_COLOR } else { FILTER_OFF_COLOR };
+                parent.spawn(
+                    (
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                                ..default()
+                            },
+                            background_color: color.into(),
+                            ..default()
+                        },
+                        EventFilterButton { category },
+                    )
+                )
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(category.name(), get_small_text_style(&asset_server))
+                    );
+                });
+            }
+        });
+
+        // Log lines
+        parent.spawn((
+            TextBundle {
+                text: Text {
+                    sections: vec![],
+                    alignment: TextAlignment::Left,
+                    ..default()
+                },
+                ..default()
+            },
+            EventLogText{},
+        ));
+    });
+}
+
+pub fn despawn_event_log(
+    mut commands: Commands,
+    event_log_query: Query<Entity, With<EventLogPanel>>,
+) {
+    if let Ok(event_log_entity) = event_log_query.get_single() {
+        commands.entity(event_log_entity).despawn_recursive();
+    }
+}
+
+// Stores every RobotEvent in the log
+pub fn record_robot_events(
+    mut robot_events: EventReader<RobotEvent>,
+    mut event_log: ResMut<EventLog>,
+) {
+    for event in robot_events.iter() {
+        event_log.push(LogEntry {
+            robot: event.robot,
+            tick: event.tick,
+            category: event.kind.category(),
+            text: event.kind.describe(),
+        });
+    }
+}
+
+// Mouse wheel over the panel scrolls through older entries
+pub fn scroll_event_log(
+    mut wheel_events: EventReader<MouseWheel>,
+    panel_query: Query<&Interaction, With<EventLogPanel>>,
+    mut event_log: ResMut<EventLog>,
+) {
+    let hovered = matches!(panel_query.get_single(), Ok(Interaction::Hovered) | Ok(Interaction::Pressed));
+    let scrolled: f32 = wheel_events.iter().map(|event| event.y).sum();
+    if !hovered || scrolled == 0.0 {
+        return;
+    }
+
+    let max_scroll = event_log.visible().count().saturating_sub(EVENT_LOG_VISIBLE_LINES);
+    let lines = scrolled.signum() as isize * scrolled.abs().ceil() as isize;
+    event_log.scroll = (event_log.scroll as isize + lines).clamp(0, max_scroll as isize) as usize;
+}
+
+pub fn interact_with_event_filter_buttons(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor, &EventFilterButton),
+        Changed<Interaction>
+        >,
+    mut event_log: ResMut<EventLog>,
+) {
+    for (interaction, mut background_color, button) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                if !event_log.enabled.remove(&button.category) {
+                    event_log.enabled.insert(button.category);
+                }
+                event_log.scroll = 0;
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {}
+        }
+        if *interaction != Interaction::Hovered {
+            let enabled = event_log.enabled.contains(&button.category);
+            *background_color = if enabled { FILTER_ON_COLOR.into() } else { FILTER_OFF_COLOR.into() };
+        }
+    }
+}
+
+pub fn update_event_log_text(
+    event_log: Res<EventLog>,
+    world: Res<WorldRes>,
+    asset_server: Res<AssetServer>,
+    mut text_query: Query<&mut Text, With<EventLogText>>,
+) {
+    if !event_log.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    // The newest entries are at the bottom, scroll moves the window up
+    let visible: Vec<&LogEntry> = event_log.visible().collect();
+    let end = visible.len().saturating_sub(event_log.scroll);
+    let start = end.saturating_sub(EVENT_LOG_VISIBLE_LINES);
+
+    text.sections = visible[start..end]
+        .iter()
+        .map(|entry| {
+            let (name, color) = world
+                .robots
+                .get(entry.robot)
+                .map_or(("?", Color::WHITE), |robot| (robot.name.as_str(), robot.color));
+            TextSection::new(
+                format!("[{:>5}] {}: {}\n", entry.tick, name, entry.text),
+                TextStyle {
+                    color,
+                    ..get_small_text_style(&asset_server)
+                },
+            )
+        })
+        .collect();
+}
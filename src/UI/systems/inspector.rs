@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::UI::components::*;
+use crate::UI::styles::*;
+use crate::camera::components::CameraMaker;
+use crate::world::coords::{robot_to_tile_pos, world_to_robot};
+use crate::WorldRes;
+
+// Distance between the cursor and the tooltip
+const TOOLTIP_OFFSET: f32 = 16.0;
+
+// Hidden until the cursor is over a tile
+pub fn spawn_tile_inspector(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section("", get_small_text_style(&asset_server)),
+            style: Style {
+                position_type: PositionType::Absolute,
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+        TileInspector{},
+    ));
+}
+
+pub fn despawn_tile_inspector(
+    mut commands: Commands,
+    inspector_query: Query<Entity, With<TileInspector>>,
+) {
+    if let Ok(inspector_entity) = inspector_query.get_single() {
+        commands.entity(inspector_entity).despawn_recursive();
+    }
+}
+
+// Shows what the robots know about the tile under the cursor
+pub fn update_tile_inspector(
+    world: Res<WorldRes>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraMaker>>,
+    mut inspector_query: Query<(&mut Text, &mut Style, &mut Visibility), With<TileInspector>>,
+) {
+    let Ok((mut text, mut style, mut visibility)) = inspector_query.get_single_mut() else {
+        return;
+    };
+    *visibility = Visibility::Hidden;
+
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let Some(cursor_world) = camera.viewport_to_world_2d(camera_transform, cursor) else {
+        return;
+    };
+
+    // The map is split in chunks, the world coordinates are the ones of the whole map
+    let Some((x, y)) = world_to_robot(cursor_world, world.world_size) else {
+        return;
+    };
+    let Some(position) = robot_to_tile_pos((x, y), world.world_size) else {
+        return;
+    };
+    let Some(Some(tile)) = world.world.as_ref().map(|known| &known[x][y]) else {
+        return;
+    };
+
+    let in_view = world.robots.iter().any(|robot| robot.in_view.contains(&(x, y)));
+    let last_seen = match world.last_seen[x][y] {
+        _ if in_view => "in view".to_string(),
+        Some(tick) => format!("tick {} ({} ticks ago)", tick, world.tick.saturating_sub(tick)),
+        None => "never".to_string(),
+    };
+    let walkable = if tile.tile_type.properties().walk() { "yes" } else { "no" };
+
+    text.sections[0].value = format!(
+        "World ({}, {}) Robot ({}, {})\n{:?}\n{:?}\nElevation: {}\nLast seen: {}\nWalkable: {}",
+        position.x, position.y, x, y, tile.tile_type, tile.content, tile.elevation, last_seen, walkable
+    );
+    style.left = Val::Px(cursor.x + TOOLTIP_OFFSET);
+    style.top = Val::Px(cursor.y + TOOLTIP_OFFSET);
+    *visibility = Visibility::Visible;
+}
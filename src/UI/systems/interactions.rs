@@ -6,6 +6,11 @@ use crate::AppState;
 use bevy::app::AppExit;
 use std::time::Duration;
 use crate::player::resources::TickTimer;
+use crate::config::SimulationConfig;
+use crate::camera::resources::CameraTarget;
+use crate::player::events::RobotCommand;
+use crate::WorldRes;
+use crate::world::resources::VisitOverlay;
 
 pub fn interact_with_play_tick_button(
     mut button_query: Query<
@@ -13,6 +18,7 @@ pub fn interact_with_play_tick_button(
         (Changed<Interaction>, With<PlayTickButton>)
         >,
     mut timer_res: ResMut<TickTimer>,
+    simulation_config: Res<SimulationConfig>,
 ) {
     if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
         match *interaction {
@@ -21,7 +27,7 @@ pub fn interact_with_play_tick_button(
                 if timer_res.timer.paused() {
                     timer_res.timer.unpause();
                 }
-                timer_res.timer.set_duration(Duration::from_secs(1));
+                timer_res.timer.set_duration(Duration::from_millis(simulation_config.tick_ms));
             },
             Interaction::Hovered => {
                 *background_color = HOVERED_BUTTON_COLOR.into();
@@ -62,12 +68,112 @@ pub fn interact_with_double_speed_button(
         (Changed<Interaction>, With<DoubleSpeedButton>)
         >,
     mut timer_res: ResMut<TickTimer>,
+    simulation_config: Res<SimulationConfig>,
 ) {
     if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
         match *interaction {
             Interaction::Pressed => {
                 *background_color = PRESSED_BUTTON_COLOR.into();
-                timer_res.timer.set_duration(Duration::from_millis(100));
+                timer_res.timer.set_duration(Duration::from_millis(simulation_config.fast_tick_ms));
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_spotlight_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<SpotlightButton>)
+        >,
+    world: Res<WorldRes>,
+    camera_target: Res<CameraTarget>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                // Used at the next tick of the robot, the result shows up in the event log
+                if let Some(robot_state) = world.robots.get(camera_target.robot) {
+                    let _ = robot_state.commands_tx.lock().unwrap().send(RobotCommand::UseTool("Spotlight"));
+                }
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_heatmap_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<HeatmapButton>)
+        >,
+    mut visit_overlay: ResMut<VisitOverlay>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                visit_overlay.heatmap = !visit_overlay.heatmap;
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_trail_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<TrailButton>)
+        >,
+    mut visit_overlay: ResMut<VisitOverlay>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                visit_overlay.trail = !visit_overlay.trail;
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_reset_visits_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ResetVisitsButton>)
+        >,
+    mut world: ResMut<WorldRes>,
+    mut visit_overlay: ResMut<VisitOverlay>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                world.reset_visits();
+                // The tile colors are only updated when something changes
+                visit_overlay.set_changed();
             },
             Interaction::Hovered => {
                 *background_color = HOVERED_BUTTON_COLOR.into();
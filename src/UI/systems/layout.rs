@@ -1,17 +1,20 @@
 use bevy::prelude::*;
 use crate::UI::components::*;
 use crate::UI::styles::*;
+use crate::main_menu::components::ThemeButton;
 use crate::WorldRes;
+use crate::player::resources::Runners;
+use crate::camera::resources::CameraTarget;
 use robotics_lib::runner::Runner;
 use robotics_lib::world::environmental_conditions::WeatherType;
 
 pub fn spawn_ui_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    runner_res: NonSendMut<Runner>,
+    runners: NonSendMut<Runners>,
     world_res: Res<WorldRes>,
 ) {
-    let ui_entity = build_ui(&mut commands, &asset_server, &runner_res, &world_res);
+    let ui_entity = build_ui(&mut commands, &asset_server, &runners, &world_res);
 }
 
 pub fn despawn_ui_menu(
@@ -26,7 +29,7 @@ pub fn despawn_ui_menu(
 pub fn build_ui(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
-    runner_res: &NonSendMut<Runner>,
+    runners: &NonSendMut<Runners>,
     world: &Res<WorldRes>,
 ) -> Entity {
 
@@ -215,6 +218,28 @@ pub fn build_ui(
                     },
                     EnergyText{},
                 ));
+
+                // Robots 
+                parent.spawn((
+                    TextBundle {
+                        text: Text {
+                            sections: world.robots
+                                .iter()
+                                .map(|robot| TextSection::new(
+                                    format!("{}\n", robot.name),
+                                    TextStyle {
+                                        color: robot.color,
+                                        ..get_button_text_style(asset_server)
+                                    },
+                                ))
+                                .collect(),
+                            alignment: TextAlignment::Left,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    RobotsText{},
+                ));
             });
 
 
@@ -387,6 +412,38 @@ pub fn build_ui(
                     }
                 );
             });
+
+             // Spotlight Button
+            parent.spawn(
+                (
+                    ButtonBundle{
+                        style: Style{
+                        height: Val::Px(40.0),
+                        padding: UiRect::horizontal(Val::Px(8.0)),
+                        justify_content: JustifyContent::Center,
+                        align_self: AlignSelf::End,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+
+                        background_color: NORMAL_BUTTON_COLOR.into(),
+                        ..default()
+                    },
+                    SpotlightButton{},
+                )
+            )
+            .with_children(|parent| {
+                // Text 
+                parent.spawn(
+                    TextBundle::from_section("Spotlight", get_small_text_style(asset_server))
+                );
+            });
+
+            // Visit overlays
+            spawn_text_button(parent, "Heatmap", HeatmapButton{}, asset_server);
+            spawn_text_button(parent, "Trail", TrailButton{}, asset_server);
+            spawn_text_button(parent, "Reset visits", ResetVisitsButton{}, asset_server);
+            spawn_text_button(parent, "Theme", ThemeButton{}, asset_server);
         });
 
     })
@@ -395,9 +452,35 @@ pub fn build_ui(
     main_menu_entity
 }
 
+// A button with a short label, like the Spotlight one
+fn spawn_text_button(parent: &mut ChildBuilder, label: &str, marker: impl Component, asset_server: &Res<AssetServer>) {
+    parent.spawn(
+        (
+            ButtonBundle{
+                style: Style{
+                    height: Val::Px(40.0),
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    justify_content: JustifyContent::Center,
+                    align_self: AlignSelf::End,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: NORMAL_BUTTON_COLOR.into(),
+                ..default()
+            },
+            marker,
+        )
+    )
+    .with_children(|parent| {
+        parent.spawn(
+            TextBundle::from_section(label, get_small_text_style(asset_server))
+        );
+    });
+}
+
 pub fn update_bottom_bar(
-    runner_res: NonSendMut<Runner>,
     world: Res<WorldRes>,
+    camera_target: Res<CameraTarget>,
     mut asset_server: Res<AssetServer>,
     mut set: ParamSet<(
         Query<&mut Text, With<ScoreText>>,
@@ -410,15 +493,17 @@ pub fn update_bottom_bar(
         Query<&mut Text, With<InventoryDraw>>
     )>,
 ) {
-    let robot = runner_res.get_robot();
-    let score = world.score;
-    let x = world.player_x;
-    let y = world.player_y;
-    let energy = robot.get_energy().get_energy_level();
-    let elevation = world.elevation;
+    // The bar shows the robot followed by the camera
+    let target = camera_target.robot.min(world.robots.len() - 1);
+    let robot_state = &world.robots[target];
+    let score = robot_state.score;
+    let x = robot_state.player_x;
+    let y = robot_state.player_y;
+    let energy = robot_state.energy;
+    let elevation = robot_state.elevation;
 
     if let Ok(mut old_text) = set.p0().get_single_mut() {
-        old_text.sections[0].value = format!("Score: {}", score);
+        old_text.sections[0].value = format!("Score: {} ({})", score, robot_state.name);
     }
     if let Ok(mut old_text) = set.p1().get_single_mut() {
         old_text.sections[0].value = format!("X: {}", x);
@@ -457,7 +542,7 @@ pub fn update_bottom_bar(
     if let Ok(mut old_text) = set.p7().get_single_mut() {
 
         // Getting the items in the inventory
-        let hash_contents = robot.get_backpack().get_contents();
+        let hash_contents = &robot_state.backpack;
         let mut index = 0;
         for (content, quantity) in hash_contents {
             if *quantity > 0 {
@@ -482,6 +567,27 @@ pub fn update_bottom_bar(
 }
 
 
+// Energy and score of every robot, the one shown in the bottom bar is marked
+pub fn update_robots_list(
+    world: Res<WorldRes>,
+    camera_target: Res<CameraTarget>,
+    mut robots_text_query: Query<&mut Text, With<RobotsText>>,
+) {
+    if let Ok(mut old_text) = robots_text_query.get_single_mut() {
+        for (id, robot_state) in world.robots.iter().enumerate() {
+            let marker = if id == camera_target.robot { ">" } else { " " };
+            old_text.sections[id].value = format!(
+                "{} {}: energy {}, score {:.1}\n",
+                marker,
+                robot_state.name,
+                robot_state.energy,
+                robot_state.score,
+            );
+        }
+    }
+}
+
+
 pub fn build_top_left_ui(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::ui::RelativeCursorPosition;
+use bevy::window::PrimaryWindow;
+use robotics_lib::world::tile::{Tile, TileType};
+
+use crate::UI::components::*;
+use crate::UI::resources::Minimap;
+use crate::camera::components::CameraMaker;
+use crate::world::coords::{robot_to_world, TILE_SIZE};
+use crate::world::events::TilesUpdated;
+use crate::WorldRes;
+
+// Side of the widget on screen, the image is scaled to fit
+const MINIMAP_SIZE: f32 = 200.0;
+const UNKNOWN_COLOR: [u8; 4] = [31, 19, 2, 255];
+const VIEWPORT_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+pub fn spawn_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    world: Res<WorldRes>,
+) {
+    // One pixel per tile
+    let size = world.world_size as u32;
+    let mut image = Image::new_fill(
+        Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &UNKNOWN_COLOR,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    // What is already known, after this only the updated tiles are drawn
+    if let Some(known) = world.world.as_ref() {
+        for (x, row) in known.iter().enumerate() {
+            for (y, tile) in row.iter().enumerate() {
+                if let Some(tile) = tile {
+                    set_pixel(&mut image, world.world_size, x as i64, y as i64, tile_type_color(&tile.tile_type));
+                }
+            }
+        }
+    }
+    let handle = images.add(image);
+
+    commands.spawn(
+        (
+            ImageBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(4.0),
+                    bottom: Val::Px(4.0),
+                    width: Val::Px(MINIMAP_SIZE),
+                    height: Val::Px(MINIMAP_SIZE),
+                    ..default()
+                },
+                image: handle.clone().into(),
+                ..default()
+            },
+            // Clicks and where they are on the image
+            Interaction::default(),
+            RelativeCursorPosition::default(),
+            MinimapImage{},
+        )
+    );
+    commands.insert_resource(Minimap { image: handle });
+}
+
+pub fn despawn_minimap(
+    mut commands: Commands,
+    minimap_query: Query<Entity, With<MinimapImage>>,
+) {
+    if let Ok(minimap_entity) = minimap_query.get_single() {
+        commands.entity(minimap_entity).despawn_recursive();
+    }
+}
+
+// Draws the tiles updated since the last frame, the robots and what the camera sees.
+// The robots and the viewport are drawn on top of the tiles, so the pixels under them
+// are remembered and get their tile back before they are drawn again
+pub fn update_minimap(
+    world: Res<WorldRes>,
+    minimap: Option<Res<Minimap>>,
+    mut images: ResMut<Assets<Image>>,
+    mut tiles_updated: EventReader<TilesUpdated>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<CameraMaker>>,
+    mut last_drawn: Local<Option<(usize, Vec3, f32)>>,
+    // Pixels covered by the robots and the viewport
+    mut overlay: Local<Vec<(i64, i64)>>,
+) {
+    let updated: Vec<(usize, usize)> = tiles_updated.iter().flat_map(|updated| updated.0.iter().copied()).collect();
+    let Some(minimap) = minimap else {
+        return;
+    };
+    let (Ok(window), Ok((camera_transform, projection))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    // Nothing moved since the last time
+    let state = (world.tick, camera_transform.translation, projection.scale);
+    if updated.is_empty() && *last_drawn == Some(state) {
+        return;
+    }
+    *last_drawn = Some(state);
+
+    let Some(image) = images.get_mut(&minimap.image) else {
+        return;
+    };
+    let Some(known) = world.world.as_ref() else {
+        return;
+    };
+    let size = world.world_size;
+
+    // Row 0 of the image is the top, like for the robot
+    for (x, y) in overlay.drain(..) {
+        set_pixel(image, size, x, y, tile_color(known, x, y));
+    }
+    for (x, y) in updated {
+        set_pixel(image, size, x as i64, y as i64, tile_color(known, x as i64, y as i64));
+    }
+
+    // The camera viewport, as a rectangle
+    let half_width = window.width() / 2.0 * projection.scale;
+    let half_height = window.height() / 2.0 * projection.scale;
+    let center = camera_transform.translation.truncate();
+    let top_left = world_to_minimap(center + Vec2::new(-half_width, half_height), size);
+    let bottom_right = world_to_minimap(center + Vec2::new(half_width, -half_height), size);
+    let (left, top) = (top_left.x.floor() as i64, top_left.y.floor() as i64);
+    let (right, bottom) = (bottom_right.x.ceil() as i64, bottom_right.y.ceil() as i64);
+    for y in left..=right {
+        for x in [top, bottom] {
+            if set_pixel(image, size, x, y, VIEWPORT_COLOR) {
+                overlay.push((x, y));
+            }
+        }
+    }
+    for x in top..=bottom {
+        for y in [left, right] {
+            if set_pixel(image, size, x, y, VIEWPORT_COLOR) {
+                overlay.push((x, y));
+            }
+        }
+    }
+
+    // The robots, 3x3 so they can be seen on big maps
+    for robot in world.robots.iter() {
+        let color = robot.color.as_rgba_u8();
+        for i in -1..=1 {
+            for j in -1..=1 {
+                let (x, y) = (robot.player_x as i64 + i, robot.player_y as i64 + j);
+                if set_pixel(image, size, x, y, color) {
+                    overlay.push((x, y));
+                }
+            }
+        }
+    }
+}
+
+// The color of a tile without anything drawn on it
+fn tile_color(known: &[Vec<Option<Tile>>], x: i64, y: i64) -> [u8; 4] {
+    let tile = known.get(x as usize).and_then(|row| row.get(y as usize)).and_then(|tile| tile.as_ref());
+    tile.map_or(UNKNOWN_COLOR, |tile| tile_type_color(&tile.tile_type))
+}
+
+// Clicking (or dragging) on the minimap moves the camera there
+pub fn interact_with_minimap(
+    world: Res<WorldRes>,
+    minimap_query: Query<(&Interaction, &RelativeCursorPosition), With<MinimapImage>>,
+    mut camera_query: Query<&mut Transform, With<CameraMaker>>,
+) {
+    let Ok((interaction, cursor)) = minimap_query.get_single() else {
+        return;
+    };
+    if *interaction != Interaction::Pressed {
+        return;
+    }
+    let Some(normalized) = cursor.normalized else {
+        return;
+    };
+    if !(0.0..=1.0).contains(&normalized.x) || !(0.0..=1.0).contains(&normalized.y) {
+        return;
+    }
+
+    let size = world.world_size;
+    let x = ((normalized.y * size as f32) as usize).min(size - 1);
+    let y = ((normalized.x * size as f32) as usize).min(size - 1);
+    let target = robot_to_world((x, y), size);
+    if let Ok(mut camera_transform) = camera_query.get_single_mut() {
+        camera_transform.translation.x = target.x;
+        camera_transform.translation.y = target.y;
+    }
+}
+
+// World coordinates to minimap pixels (column, row), not clamped
+fn world_to_minimap(position: Vec2, size: usize) -> Vec2 {
+    let offset = (size as f32 - 1.0) * TILE_SIZE / 2.0;
+    let column = (position.x + offset) / TILE_SIZE;
+    let row = size as f32 - 1.0 - (position.y + offset) / TILE_SIZE;
+    Vec2::new(column + 0.5, row + 0.5)
+}
+
+// False when the pixel is outside of the image
+fn set_pixel(image: &mut Image, size: usize, x: i64, y: i64, color: [u8; 4]) -> bool {
+    if x < 0 || y < 0 || x as usize >= size || y as usize >= size {
+        return false;
+    }
+    let index = (x as usize * size + y as usize) * 4;
+    image.data[index..index + 4].copy_from_slice(&color);
+    true
+}
+
+// Roughly the main color of each tile in tiles4.png
+fn tile_type_color(tile_type: &TileType) -> [u8; 4] {
+    match tile_type {
+        TileType::DeepWater => [20, 50, 140, 255],
+        TileType::ShallowWater => [60, 120, 200, 255],
+        TileType::Sand => [220, 200, 130, 255],
+        TileType::Grass => [70, 160, 60, 255],
+        TileType::Street => [120, 120, 120, 255],
+        TileType::Hill => [120, 150, 70, 255],
+        TileType::Mountain => [110, 90, 70, 255],
+        TileType::Snow => [240, 240, 250, 255],
+        TileType::Lava => [220, 80, 20, 255],
+        TileType::Teleport(_) => [180, 80, 220, 255],
+        TileType::Wall => [60, 40, 30, 255],
+        _ => UNKNOWN_COLOR,
+    }
+}
@@ -1,3 +1,8 @@
 use bevy::prelude::*;
 pub mod layout;
 pub mod interactions;
+pub mod event_log;
+pub mod debug_overlay;
+pub mod elevation_legend;
+pub mod minimap;
+pub mod inspector;
@@ -3,16 +3,20 @@ use crate::camera::systems::spawn_camera;
 use crate::camera::systems::zoom_scalingmode;
 use crate::camera::systems::follow_robot;
 use crate::camera::systems::camera_movement;
+use crate::camera::systems::select_next_robot;
+use crate::camera::resources::CameraTarget;
 use crate::AppState;
 
 pub mod components;
 pub mod systems;
+pub mod resources;
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<CameraTarget>()
             // Startup
             .add_systems(Startup, spawn_camera)
             // Update 
@@ -21,6 +25,7 @@ impl Plugin for CameraPlugin {
                     zoom_scalingmode, 
                     follow_robot,
                     camera_movement,
+                    select_next_robot,
                 )
                 .run_if(in_state(AppState::Game))
             );
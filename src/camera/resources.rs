@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+// The robot followed by the camera and shown in the bottom bar
+#[derive(Resource, Default)]
+pub struct CameraTarget {
+    pub robot: usize,
+}
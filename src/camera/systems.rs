@@ -5,24 +5,27 @@ use crate::camera::components::CameraMaker;
 use crate::MyRobot;
 use crate::player::components::Player;
 use bevy::core_pipeline::clear_color::ClearColorConfig;
-
-pub const CAMERA_SPEED : f32 = 250.0;
+use crate::config::{KeyBindings, RenderConfig};
+use crate::camera::resources::CameraTarget;
+use crate::world::resources::WorldRes;
 
 pub fn zoom_scalingmode(
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    render_config: Res<RenderConfig>,
     mut camera_query: Query<&mut OrthographicProjection, With<CameraMaker>>,
 ) { 
 
-    if keyboard_input.pressed(KeyCode::P) {
+    if key_bindings.zoom_in.pressed(&keyboard_input) {
         let mut projection = camera_query.single_mut();
         // Zoom in 
-        projection.scale /= 1.03;
+        projection.scale /= render_config.zoom_factor;
     }
 
-    if keyboard_input.pressed(KeyCode::Minus) {
+    if key_bindings.zoom_out.pressed(&keyboard_input) {
         let mut projection = camera_query.single_mut();
-        // Zoom in 
-        projection.scale *= 1.03;
+        // Zoom out 
+        projection.scale *= render_config.zoom_factor;
     }
 
  }
@@ -30,6 +33,7 @@ pub fn zoom_scalingmode(
 pub fn spawn_camera(
     mut commands: Commands,
     window_query: Query<&Window, With<PrimaryWindow>>,
+    render_config: Res<RenderConfig>,
 ) {
     let window = window_query.get_single().unwrap();
     let mut camera = Camera2dBundle {
@@ -37,7 +41,7 @@ pub fn spawn_camera(
         ..default()
     };
     // Zooming 
-    camera.projection.scale /= 5.0;
+    camera.projection.scale /= render_config.initial_zoom;
     camera.camera_2d.clear_color = ClearColorConfig::Custom(Color::rgba_u8(31, 19, 2, 1));
     commands.spawn(
         (
@@ -49,21 +53,41 @@ pub fn spawn_camera(
 
 pub fn follow_robot(
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    camera_target: Res<CameraTarget>,
     mut camera_transform_query: Query<&mut Transform, With<CameraMaker>>,
-    robot_transform_query: Query<&Transform, (With<Player>, Without<CameraMaker>)>,
+    robot_transform_query: Query<(&Transform, &Player), Without<CameraMaker>>,
 ) {
-    if keyboard_input.pressed(KeyCode::F) {
+    if key_bindings.follow_robot.pressed(&keyboard_input) {
         let mut camera_transform = camera_transform_query.get_single_mut().unwrap();
-        let robot_transform = robot_transform_query.get_single().unwrap();
+        let target = robot_transform_query
+            .iter()
+            .find(|(_, player)| player.id == camera_target.robot);
+
+        if let Some((robot_transform, _)) = target {
+            camera_transform.translation.x = robot_transform.translation.x;
+            camera_transform.translation.y = robot_transform.translation.y;
+        }
+    }
+}
 
-        camera_transform.translation.x = robot_transform.translation.x;
-        camera_transform.translation.y = robot_transform.translation.y;
+// Cycles the robot followed by the camera
+pub fn select_next_robot(
+    keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    world_res: Res<WorldRes>,
+    mut camera_target: ResMut<CameraTarget>,
+) {
+    if key_bindings.next_robot.just_pressed(&keyboard_input) && !world_res.robots.is_empty() {
+        camera_target.robot = (camera_target.robot + 1) % world_res.robots.len();
     }
 }
 
 
 pub fn camera_movement(
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    render_config: Res<RenderConfig>,
     // A query matches only the entities that fit the specification
     // It returns an iterator
     mut camera_query: Query<&mut Transform, With<CameraMaker>>,
@@ -77,16 +101,16 @@ pub fn camera_movement(
         let mut direction = Vec3::ZERO;
 
         // This is how you register keyboard input
-        if keyboard_input.pressed(KeyCode::Left) || keyboard_input.pressed(KeyCode::A) {
+        if key_bindings.camera_left.pressed(&keyboard_input) {
             direction += Vec3::new(-1.0, 0.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Right) || keyboard_input.pressed(KeyCode::D) {
+        if key_bindings.camera_right.pressed(&keyboard_input) {
             direction += Vec3::new(1.0, 0.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Up) || keyboard_input.pressed(KeyCode::W) {
+        if key_bindings.camera_up.pressed(&keyboard_input) {
             direction += Vec3::new(0.0, 1.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Down) || keyboard_input.pressed(KeyCode::S) {
+        if key_bindings.camera_down.pressed(&keyboard_input) {
             direction += Vec3::new(0.0, -1.0, 0.0);
         }
 
@@ -98,7 +122,7 @@ pub fn camera_movement(
         }
 
         // Adding the direction
-        transform.translation += direction * CAMERA_SPEED * time.delta_seconds();
+        transform.translation += direction * render_config.camera_speed * time.delta_seconds();
     }
 }
 
@@ -0,0 +1,149 @@
+use crate::config::{Config, ConfigError, RobotConfig};
+use crate::headless::HeadlessOptions;
+use crate::world::resources::{ContentPreset, GeneratorKind, Seed};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const USAGE: &str = "\
+Usage: UI_rust [OPTIONS]
+
+Options:
+  --config <PATH>        Config file, defaults to config.ron if it exists
+  --generator <GEN>      Where the world comes from: oxag, file, procedural
+  --world-file <PATH>    World file loaded by the file generator
+  --seed <SEED>          World seed, a number or \"random\"
+  --size <SIZE>          World size, the world is SIZE x SIZE tiles
+  --preset <PRESET>      Content preset: default, garbage, sparse
+  --tick-ms <MS>         Time between two ticks at normal speed
+  --fast-tick-ms <MS>    Time between two ticks at fast speed
+  --camera-speed <PX>    Camera speed in pixels per second
+  --zoom-factor <F>      Zoom step, greater than 1
+  --log-level <LEVEL>    error, warn, info, debug or trace
+  --robots <N>           Number of robots, the ones missing from the config use the default brain
+  --headless             Run the simulation without a window and print a summary at the end
+  --ticks <N>            Ticks of the headless run, defaults to 1000
+  --replay <PATH>        Write the position, energy and score of every robot at every tick (headless only)
+  -h, --help             Print this message
+
+Options given on the command line override the ones in the config file.";
+
+const DEFAULT_HEADLESS_TICKS: usize = 1000;
+
+// Everything the command line decides
+pub struct CliOptions {
+    pub config: Config,
+    // Some when the game has to run without a window
+    pub headless: Option<HeadlessOptions>,
+}
+
+// Why parse_args did not return options
+pub enum CliError {
+    // -h or --help, the usage has to be printed and the game must not start
+    Help,
+    Invalid(String),
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Invalid(message)
+    }
+}
+
+// Loads the config file and applies the command line arguments (without the program name) on top of it
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliOptions, CliError> {
+    let args: Vec<String> = args.into_iter().collect();
+
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        return Err(CliError::Help);
+    }
+
+    // The config file has to be loaded first, so the other arguments can override it
+    let config_path = match args.iter().position(|arg| arg == "--config") {
+        Some(index) => Some(PathBuf::from(
+            args.get(index + 1).ok_or_else(|| "missing value for '--config'".to_string())?,
+        )),
+        None => None,
+    };
+    let mut config = Config::load(config_path.as_deref()).map_err(|error| error.to_string())?;
+
+    let mut headless = false;
+    let mut ticks = DEFAULT_HEADLESS_TICKS;
+    let mut replay = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                next_value(&mut args, &arg)?;
+            }
+            "--generator" => {
+                config.world.generator = next_value(&mut args, &arg)?.parse::<GeneratorKind>()?;
+            }
+            "--world-file" => {
+                config.world.world_file = Some(PathBuf::from(next_value(&mut args, &arg)?));
+            }
+            "--seed" => {
+                config.world.seed = next_value(&mut args, &arg)?.parse::<Seed>()?;
+            }
+            "--size" => {
+                config.world.world_size = parse_number(&mut args, &arg)?;
+            }
+            "--preset" => {
+                config.world.content_preset = next_value(&mut args, &arg)?.parse::<ContentPreset>()?;
+            }
+            "--tick-ms" => {
+                config.simulation.tick_ms = parse_number(&mut args, &arg)?;
+            }
+            "--fast-tick-ms" => {
+                config.simulation.fast_tick_ms = parse_number(&mut args, &arg)?;
+            }
+            "--camera-speed" => {
+                config.render.camera_speed = parse_number(&mut args, &arg)?;
+            }
+            "--zoom-factor" => {
+                config.render.zoom_factor = parse_number(&mut args, &arg)?;
+            }
+            "--robots" => {
+                let count: usize = parse_number(&mut args, &arg)?;
+                config.robots.truncate(count);
+                while config.robots.len() < count {
+                    config.robots.push(RobotConfig::numbered(config.robots.len()));
+                }
+            }
+            "--log-level" => {
+                config.log.level = next_value(&mut args, &arg)?;
+            }
+            "--headless" => {
+                headless = true;
+            }
+            "--ticks" => {
+                ticks = parse_number(&mut args, &arg)?;
+            }
+            "--replay" => {
+                replay = Some(PathBuf::from(next_value(&mut args, &arg)?));
+            }
+            _ => return Err(CliError::Invalid(format!("unknown argument '{}'\n\n{}", arg, USAGE))),
+        }
+    }
+
+    config.validate().map_err(|error: ConfigError| error.to_string())?;
+    if !headless && replay.is_some() {
+        return Err(CliError::Invalid("'--replay' only works with '--headless'".to_string()));
+    }
+
+    Ok(CliOptions {
+        config,
+        headless: headless.then(|| HeadlessOptions { ticks, replay }),
+    })
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, name: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("missing value for '{}'", name))
+}
+
+fn parse_number<T: FromStr>(args: &mut impl Iterator<Item = String>, name: &str) -> Result<T, String> {
+    let value = next_value(args, name)?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid value '{}' for '{}', expected a number", value, name))
+}
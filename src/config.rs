@@ -0,0 +1,630 @@
+use bevy::prelude::*;
+use bevy::log::Level;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::world::resources::WorldSettings;
+use crate::tools::ToolConfig;
+
+// Loaded when no --config is given, if it exists
+pub const DEFAULT_CONFIG_PATH: &str = "config.ron";
+
+// Everything that can be tuned without recompiling.
+// Every field has a default, so a config file only needs the values it changes
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub world: WorldSettings,
+    pub simulation: SimulationConfig,
+    pub render: RenderConfig,
+    pub log: LogConfig,
+    pub keys: KeyConfig,
+    // Every robot explores its own copy of the same world
+    pub robots: Vec<RobotConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            world: WorldSettings::default(),
+            simulation: SimulationConfig::default(),
+            render: RenderConfig::default(),
+            log: LogConfig::default(),
+            keys: KeyConfig::default(),
+            robots: vec![RobotConfig::numbered(0)],
+        }
+    }
+}
+
+// Which brain drives a robot
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum BrainConfig {
+    // The hand written policy
+    Heuristic,
+    // A network saved with Network::save
+    Network(PathBuf),
+    // An untrained network, useful as a baseline
+    RandomNetwork,
+}
+
+// Sprite tints given to robots that don't choose one
+const ROBOT_COLORS: [[f32; 3]; 6] = [
+    [1.0, 1.0, 1.0],
+    [1.0, 0.5, 0.5],
+    [0.5, 0.7, 1.0],
+    [0.6, 1.0, 0.6],
+    [1.0, 0.9, 0.4],
+    [0.9, 0.6, 1.0],
+];
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RobotConfig {
+    pub name: String,
+    #[serde(default = "default_brain")]
+    pub brain: BrainConfig,
+    // Tint of the sprite, rgb between 0 and 1
+    #[serde(default = "default_color")]
+    pub color: [f32; 3],
+    // Tools the policy can use, in order of preference
+    #[serde(default)]
+    pub tools: Vec<ToolConfig>,
+}
+
+fn default_brain() -> BrainConfig {
+    BrainConfig::Heuristic
+}
+
+fn default_color() -> [f32; 3] {
+    ROBOT_COLORS[0]
+}
+
+// The robots of the session, inserted as a resource
+#[derive(Resource, Clone, Debug)]
+pub struct RobotsConfig(pub Vec<RobotConfig>);
+
+impl RobotConfig {
+    // The robot number `index` when they are not described one by one
+    pub fn numbered(index: usize) -> Self {
+        RobotConfig {
+            name: format!("Robot {}", index + 1),
+            brain: BrainConfig::Heuristic,
+            color: ROBOT_COLORS[index % ROBOT_COLORS.len()],
+            tools: vec![],
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        Color::rgb(self.color[0], self.color[1], self.color[2])
+    }
+}
+
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    // Time between two ticks at normal speed
+    pub tick_ms: u64,
+    // Time between two ticks at fast speed
+    pub fast_tick_ms: u64,
+    // A robot waits when this many of its messages are not drawn yet
+    pub max_queued_messages: usize,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            tick_ms: 1000,
+            fast_tick_ms: 100,
+            max_queued_messages: 5000,
+        }
+    }
+}
+
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderConfig {
+    // Pixels per second when moving the camera
+    pub camera_speed: f32,
+    // How much the camera zooms every frame the zoom key is held
+    pub zoom_factor: f32,
+    // The camera starts zoomed in by this amount
+    pub initial_zoom: f32,
+    // Ticks after which a remembered tile is as dark as it gets
+    pub fog_fade_ticks: usize,
+    // Brightness of the tiles remembered from the last tick, between 0 and 1
+    pub fog_max_brightness: f32,
+    // Brightness of the oldest remembered tiles, between 0 and fog_max_brightness
+    pub fog_min_brightness: f32,
+    // Height difference between two contour lines of the elevation overlay
+    pub contour_interval: usize,
+    // How many of the last positions of a robot the trail overlay draws
+    pub trail_length: usize,
+    // Name of the theme to start with, from the files in assets/themes
+    pub theme: String,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        RenderConfig {
+            camera_speed: 250.0,
+            zoom_factor: 1.03,
+            initial_zoom: 5.0,
+            fog_fade_ticks: 2000,
+            fog_max_brightness: 0.85,
+            fog_min_brightness: 0.35,
+            contour_interval: 10,
+            trail_length: 50,
+            theme: "classic".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    // One of: error, warn, info, debug, trace
+    pub level: String,
+    pub filter: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: "error".to_string(),
+            filter: "mygame=debug".to_string(),
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn level(&self) -> Level {
+        self.level.parse::<Level>().unwrap_or(Level::ERROR)
+    }
+}
+
+// Key names as written in the config file, see parse_key for the accepted names
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyConfig {
+    pub camera_up: Vec<String>,
+    pub camera_down: Vec<String>,
+    pub camera_left: Vec<String>,
+    pub camera_right: Vec<String>,
+    pub follow_robot: Vec<String>,
+    pub zoom_in: Vec<String>,
+    pub zoom_out: Vec<String>,
+    pub pause: Vec<String>,
+    pub unpause: Vec<String>,
+    pub fast_speed: Vec<String>,
+    pub normal_speed: Vec<String>,
+    pub start_game: Vec<String>,
+    pub exit: Vec<String>,
+    pub next_robot: Vec<String>,
+    pub debug_overlay: Vec<String>,
+    pub elevation_overlay: Vec<String>,
+}
+
+impl Default for KeyConfig {
+    fn default() -> Self {
+        let keys = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        KeyConfig {
+            camera_up: keys(&["W", "Up"]),
+            camera_down: keys(&["S", "Down"]),
+            camera_left: keys(&["A", "Left"]),
+            camera_right: keys(&["D", "Right"]),
+            follow_robot: keys(&["F"]),
+            zoom_in: keys(&["Plus", "Equals", "NumpadAdd"]),
+            zoom_out: keys(&["Minus"]),
+            pause: keys(&["P"]),
+            unpause: keys(&["O"]),
+            fast_speed: keys(&["Z"]),
+            normal_speed: keys(&["X"]),
+            start_game: keys(&["G"]),
+            exit: keys(&["Escape"]),
+            next_robot: keys(&["Tab"]),
+            debug_overlay: keys(&["F3"]),
+            elevation_overlay: keys(&["H"]),
+        }
+    }
+}
+
+// A set of keys that all trigger the same action
+#[derive(Clone, Debug, Default)]
+pub struct Binding(pub Vec<KeyCode>);
+
+impl Binding {
+    pub fn pressed(&self, input: &Input<KeyCode>) -> bool {
+        input.any_pressed(self.0.iter().copied())
+    }
+
+    pub fn just_pressed(&self, input: &Input<KeyCode>) -> bool {
+        input.any_just_pressed(self.0.iter().copied())
+    }
+}
+
+// The key bindings used by the systems, built from the KeyConfig
+#[derive(Resource, Clone, Debug)]
+pub struct KeyBindings {
+    pub camera_up: Binding,
+    pub camera_down: Binding,
+    pub camera_left: Binding,
+    pub camera_right: Binding,
+    pub follow_robot: Binding,
+    pub zoom_in: Binding,
+    pub zoom_out: Binding,
+    pub pause: Binding,
+    pub unpause: Binding,
+    pub fast_speed: Binding,
+    pub normal_speed: Binding,
+    pub start_game: Binding,
+    pub exit: Binding,
+    // Switches the robot followed by the camera and shown in the bottom bar
+    pub next_robot: Binding,
+    pub debug_overlay: Binding,
+    pub elevation_overlay: Binding,
+}
+
+impl KeyConfig {
+    pub fn bindings(&self) -> Result<KeyBindings, ConfigError> {
+        let binding = |action: &str, names: &[String]| -> Result<Binding, ConfigError> {
+            names
+                .iter()
+                .map(|name| {
+                    parse_key(name).ok_or_else(|| {
+                        ConfigError::Invalid(format!("keys.{}: unknown key '{}'", action, name))
+                    })
+                })
+                .collect::<Result<Vec<KeyCode>, ConfigError>>()
+                .map(Binding)
+        };
+
+        self.check_duplicates()?;
+
+        Ok(KeyBindings {
+            camera_up: binding("camera_up", &self.camera_up)?,
+            camera_down: binding("camera_down", &self.camera_down)?,
+            camera_left: binding("camera_left", &self.camera_left)?,
+            camera_right: binding("camera_right", &self.camera_right)?,
+            follow_robot: binding("follow_robot", &self.follow_robot)?,
+            zoom_in: binding("zoom_in", &self.zoom_in)?,
+            zoom_out: binding("zoom_out", &self.zoom_out)?,
+            pause: binding("pause", &self.pause)?,
+            unpause: binding("unpause", &self.unpause)?,
+            fast_speed: binding("fast_speed", &self.fast_speed)?,
+            normal_speed: binding("normal_speed", &self.normal_speed)?,
+            start_game: binding("start_game", &self.start_game)?,
+            exit: binding("exit", &self.exit)?,
+            next_robot: binding("next_robot", &self.next_robot)?,
+            debug_overlay: binding("debug_overlay", &self.debug_overlay)?,
+            elevation_overlay: binding("elevation_overlay", &self.elevation_overlay)?,
+        })
+    }
+
+    // A key bound to two actions would trigger both of them every time it's pressed
+    fn check_duplicates(&self) -> Result<(), ConfigError> {
+        let actions: [(&str, &[String]); 16] = [
+            ("camera_up", &self.camera_up),
+            ("camera_down", &self.camera_down),
+            ("camera_left", &self.camera_left),
+            ("camera_right", &self.camera_right),
+            ("follow_robot", &self.follow_robot),
+            ("zoom_in", &self.zoom_in),
+            ("zoom_out", &self.zoom_out),
+            ("pause", &self.pause),
+            ("unpause", &self.unpause),
+            ("fast_speed", &self.fast_speed),
+            ("normal_speed", &self.normal_speed),
+            ("start_game", &self.start_game),
+            ("exit", &self.exit),
+            ("next_robot", &self.next_robot),
+            ("debug_overlay", &self.debug_overlay),
+            ("elevation_overlay", &self.elevation_overlay),
+        ];
+
+        let mut used: HashMap<KeyCode, &str> = HashMap::new();
+        for (action, names) in actions {
+            for name in names {
+                // Unknown names are reported by bindings
+                let key = match parse_key(name) {
+                    Some(key) => key,
+                    None => continue,
+                };
+                match used.insert(key, action) {
+                    Some(other) if other != action => {
+                        return Err(ConfigError::Invalid(format!(
+                            "keys: '{}' is bound to both {} and {}",
+                            name, other, action
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, error) => write!(f, "cannot read config file '{}': {}", path.display(), error),
+            ConfigError::Parse(path, error) => write!(f, "error in config file '{}' at {}", path.display(), error),
+            ConfigError::Invalid(message) => write!(f, "invalid config: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // Loads the given file, or the default one if it exists, or falls back to the defaults
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let default_path = PathBuf::from(DEFAULT_CONFIG_PATH);
+                if !default_path.is_file() {
+                    return Ok(Config::default());
+                }
+                default_path
+            }
+        };
+
+        let text = fs::read_to_string(&path).map_err(|error| ConfigError::Io(path.clone(), error))?;
+        ron::from_str(&text).map_err(|error| ConfigError::Parse(path, error))
+    }
+
+    // Checks the values that would otherwise break the game at runtime
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.world.validate().map_err(|message| ConfigError::Invalid(format!("world: {}", message)))?;
+
+        if self.simulation.tick_ms == 0 || self.simulation.fast_tick_ms == 0 {
+            return Err(ConfigError::Invalid("simulation: tick durations must be greater than 0".to_string()));
+        }
+        if self.simulation.max_queued_messages == 0 {
+            return Err(ConfigError::Invalid("simulation.max_queued_messages must be greater than 0".to_string()));
+        }
+        if !(self.render.camera_speed > 0.0) {
+            return Err(ConfigError::Invalid("render.camera_speed must be greater than 0".to_string()));
+        }
+        if !(self.render.zoom_factor > 1.0) {
+            return Err(ConfigError::Invalid("render.zoom_factor must be greater than 1".to_string()));
+        }
+        if !(self.render.initial_zoom > 0.0) {
+            return Err(ConfigError::Invalid("render.initial_zoom must be greater than 0".to_string()));
+        }
+        if self.render.fog_fade_ticks == 0 {
+            return Err(ConfigError::Invalid("render.fog_fade_ticks must be greater than 0".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.render.fog_max_brightness) {
+            return Err(ConfigError::Invalid("render.fog_max_brightness must be between 0 and 1".to_string()));
+        }
+        if !(0.0..=self.render.fog_max_brightness).contains(&self.render.fog_min_brightness) {
+            return Err(ConfigError::Invalid(
+                "render.fog_min_brightness must be between 0 and render.fog_max_brightness".to_string(),
+            ));
+        }
+        if self.render.contour_interval == 0 {
+            return Err(ConfigError::Invalid("render.contour_interval must be greater than 0".to_string()));
+        }
+        if self.render.trail_length == 0 {
+            return Err(ConfigError::Invalid("render.trail_length must be greater than 0".to_string()));
+        }
+        if self.log.level.parse::<Level>().is_err() {
+            return Err(ConfigError::Invalid(format!(
+                "log.level: unknown level '{}', expected one of: error, warn, info, debug, trace",
+                self.log.level
+            )));
+        }
+        self.keys.bindings()?;
+
+        if self.robots.is_empty() {
+            return Err(ConfigError::Invalid("robots: at least one robot is needed".to_string()));
+        }
+        for robot in &self.robots {
+            if robot.color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                return Err(ConfigError::Invalid(format!("robots: the color of '{}' must be between 0 and 1", robot.name)));
+            }
+            if let BrainConfig::Network(path) = &robot.brain {
+                if !path.is_file() {
+                    return Err(ConfigError::Invalid(format!(
+                        "robots: the network of '{}' does not exist: '{}'",
+                        robot.name,
+                        path.display()
+                    )));
+                }
+            }
+            for tool in &robot.tools {
+                tool.validate()
+                    .map_err(|error| ConfigError::Invalid(format!("robots: '{}': {}", robot.name, error)))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Maps the key names used in the config file to bevy KeyCodes
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let key = match name {
+        "A" => KeyCode::A,
+        "B" => KeyCode::B,
+        "C" => KeyCode::C,
+        "D" => KeyCode::D,
+        "E" => KeyCode::E,
+        "F" => KeyCode::F,
+        "G" => KeyCode::G,
+        "H" => KeyCode::H,
+        "I" => KeyCode::I,
+        "J" => KeyCode::J,
+        "K" => KeyCode::K,
+        "L" => KeyCode::L,
+        "M" => KeyCode::M,
+        "N" => KeyCode::N,
+        "O" => KeyCode::O,
+        "P" => KeyCode::P,
+        "Q" => KeyCode::Q,
+        "R" => KeyCode::R,
+        "S" => KeyCode::S,
+        "T" => KeyCode::T,
+        "U" => KeyCode::U,
+        "V" => KeyCode::V,
+        "W" => KeyCode::W,
+        "X" => KeyCode::X,
+        "Y" => KeyCode::Y,
+        "Z" => KeyCode::Z,
+        "0" => KeyCode::Key0,
+        "1" => KeyCode::Key1,
+        "2" => KeyCode::Key2,
+        "3" => KeyCode::Key3,
+        "4" => KeyCode::Key4,
+        "5" => KeyCode::Key5,
+        "6" => KeyCode::Key6,
+        "7" => KeyCode::Key7,
+        "8" => KeyCode::Key8,
+        "9" => KeyCode::Key9,
+        "F1" => KeyCode::F1,
+        "F2" => KeyCode::F2,
+        "F3" => KeyCode::F3,
+        "F4" => KeyCode::F4,
+        "F5" => KeyCode::F5,
+        "F6" => KeyCode::F6,
+        "F7" => KeyCode::F7,
+        "F8" => KeyCode::F8,
+        "F9" => KeyCode::F9,
+        "F10" => KeyCode::F10,
+        "F11" => KeyCode::F11,
+        "F12" => KeyCode::F12,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Escape" => KeyCode::Escape,
+        "Space" => KeyCode::Space,
+        "Tab" => KeyCode::Tab,
+        "Enter" => KeyCode::Return,
+        "Backspace" => KeyCode::Back,
+        "Minus" => KeyCode::Minus,
+        "Plus" => KeyCode::Plus,
+        "Equals" => KeyCode::Equals,
+        "NumpadAdd" => KeyCode::NumpadAdd,
+        "NumpadSubtract" => KeyCode::NumpadSubtract,
+        "Comma" => KeyCode::Comma,
+        "Period" => KeyCode::Period,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The message of the error returned by validate
+    fn invalid(config: &Config) -> String {
+        config.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn key_names() {
+        assert_eq!(parse_key("W"), Some(KeyCode::W));
+        assert_eq!(parse_key("F3"), Some(KeyCode::F3));
+        assert_eq!(parse_key("Enter"), Some(KeyCode::Return));
+        assert_eq!(parse_key("NumpadAdd"), Some(KeyCode::NumpadAdd));
+    }
+
+    #[test]
+    fn unknown_key_names() {
+        // Names are case sensitive, like in the config file
+        assert_eq!(parse_key("w"), None);
+        assert_eq!(parse_key("Return"), None);
+        assert_eq!(parse_key(""), None);
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn unknown_key_in_a_binding() {
+        let mut config = Config::default();
+        config.keys.pause = vec!["P".to_string(), "Pause".to_string()];
+        assert!(invalid(&config).contains("keys.pause: unknown key 'Pause'"));
+    }
+
+    #[test]
+    fn key_bound_to_two_actions() {
+        let mut config = Config::default();
+        config.keys.zoom_in = vec!["P".to_string()];
+        let message = invalid(&config);
+        assert!(message.contains("'P'"));
+        assert!(message.contains("zoom_in") && message.contains("pause"));
+    }
+
+    #[test]
+    fn same_key_twice_in_one_action() {
+        let mut config = Config::default();
+        config.keys.exit = vec!["Escape".to_string(), "Escape".to_string()];
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn unknown_field_in_the_file() {
+        assert!(ron::from_str::<Config>("(render: (zoom: 2.0))").is_err());
+        assert!(ron::from_str::<Config>("(render: (zoom_factor: 2.0))").is_ok());
+    }
+
+    #[test]
+    fn out_of_range_values() {
+        let mut config = Config::default();
+        config.simulation.tick_ms = 0;
+        assert!(invalid(&config).contains("tick durations"));
+
+        let mut config = Config::default();
+        config.render.zoom_factor = 1.0;
+        assert!(invalid(&config).contains("zoom_factor"));
+
+        let mut config = Config::default();
+        config.render.camera_speed = f32::NAN;
+        assert!(invalid(&config).contains("camera_speed"));
+
+        let mut config = Config::default();
+        config.render.fog_min_brightness = 1.5;
+        assert!(invalid(&config).contains("fog_min_brightness"));
+
+        // The tiles would get brighter with age
+        let mut config = Config::default();
+        config.render.fog_max_brightness = 0.5;
+        config.render.fog_min_brightness = 0.6;
+        assert!(invalid(&config).contains("fog_min_brightness"));
+
+        let mut config = Config::default();
+        config.world.world_size = 0;
+        assert!(invalid(&config).contains("world size"));
+
+        let mut config = Config::default();
+        config.robots[0].color = [1.0, -0.1, 0.0];
+        assert!(invalid(&config).contains("color"));
+
+        let mut config = Config::default();
+        config.robots.clear();
+        assert!(invalid(&config).contains("at least one robot"));
+    }
+
+    #[test]
+    fn unknown_log_level() {
+        let mut config = Config::default();
+        config.log.level = "verbose".to_string();
+        assert!(invalid(&config).contains("log.level"));
+    }
+}
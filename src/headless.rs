@@ -0,0 +1,157 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use serde::Serialize;
+use robotics_lib::world::tile::Content;
+
+use crate::config::Config;
+use crate::player::events::RobotEventKind;
+use crate::player::messages::SimMessage;
+use crate::player::resources::build_runners;
+use crate::world::resources::RobotState;
+
+// robotics_lib recharges the robots over time, a robot is only stopped when its action
+// failed for lack of energy this many ticks in a row
+const OUT_OF_ENERGY_TICKS: usize = 100;
+
+// What the --headless run needs on top of the config
+pub struct HeadlessOptions {
+    // Stops after this many ticks, unless every robot ran out of energy before
+    pub ticks: usize,
+    // One line per robot per tick is written here
+    pub replay: Option<PathBuf>,
+}
+
+// One line of the replay file
+#[derive(Serialize)]
+struct ReplayFrame {
+    tick: usize,
+    robot: usize,
+    row: usize,
+    col: usize,
+    energy: usize,
+    score: f32,
+}
+
+// What is printed at the end of the run, for every robot
+#[derive(Default)]
+struct RobotSummary {
+    ticks: usize,
+    score: f32,
+    garbage_collected: usize,
+    tiles_discovered: HashSet<(usize, usize)>,
+    energy_used: usize,
+    // Ticks in a row whose action failed because the robot had not enough energy
+    failed_in_a_row: usize,
+}
+
+impl RobotSummary {
+    // Reads everything the robot sent during the last tick
+    fn update(&mut self, robot_state: &RobotState) {
+        let mut failed = false;
+        while let Ok(message) = robot_state.rx.lock().unwrap().try_recv() {
+            robot_state.queue_depth.received(1);
+            match message {
+                SimMessage::ScoreChanged(score) => self.score = score,
+                SimMessage::TilesRevealed(tiles) => self.tiles_discovered.extend(tiles.into_iter().map(|(coords, _)| coords)),
+                _ => {}
+            }
+        }
+
+        while let Ok((_, kind)) = robot_state.events_rx.lock().unwrap().try_recv() {
+            match kind {
+                RobotEventKind::EnergyConsumed(amount) => self.energy_used += amount,
+                RobotEventKind::AddedToBackpack(Content::Garbage(_), quantity) => self.garbage_collected += quantity,
+                RobotEventKind::NotEnoughEnergy(_) => failed = true,
+                _ => {}
+            }
+        }
+        self.failed_in_a_row = if failed { self.failed_in_a_row + 1 } else { 0 };
+    }
+
+    fn out_of_energy(&self) -> bool {
+        self.failed_in_a_row >= OUT_OF_ENERGY_TICKS
+    }
+}
+
+// Runs the simulation without a window, as fast as possible, and prints a summary
+pub fn run(config: &Config, options: &HeadlessOptions) -> Result<(), String> {
+    let seed = config.world.seed.resolve();
+    println!(
+        "Headless run: {} generator, seed {}, size {}, {} robot(s), {} ticks",
+        config.world.generator.name(), seed, config.world.world_size, config.robots.len(), options.ticks
+    );
+
+    let (mut runners, world_res) = build_runners(&config.world, &config.robots, seed)?;
+
+    let mut replay = match &options.replay {
+        Some(path) => {
+            let file = File::create(path)
+                .map_err(|error| format!("cannot create replay file '{}': {}", path.display(), error))?;
+            let mut writer = BufWriter::new(file);
+            writeln!(writer, "// seed {}, generator {}, size {}", seed, config.world.generator.name(), world_res.world_size)
+                .map_err(|error| error.to_string())?;
+            Some(writer)
+        }
+        None => None,
+    };
+
+    let mut summaries: Vec<RobotSummary> = world_res.robots.iter().map(|_| RobotSummary::default()).collect();
+    let mut out_of_energy = vec![false; runners.0.len()];
+    let start = Instant::now();
+
+    for tick in 1..=options.ticks {
+        for (id, runner) in runners.0.iter_mut().enumerate() {
+            if out_of_energy[id] {
+                continue;
+            }
+            let _ = runner.game_tick();
+            summaries[id].ticks = tick;
+            summaries[id].update(&world_res.robots[id]);
+
+            let robot = runner.get_robot();
+            let energy = robot.get_energy().get_energy_level();
+            out_of_energy[id] = summaries[id].out_of_energy();
+
+            if let Some(writer) = replay.as_mut() {
+                let frame = ReplayFrame {
+                    tick,
+                    robot: id,
+                    row: robot.get_coordinate().get_row(),
+                    col: robot.get_coordinate().get_col(),
+                    energy,
+                    score: summaries[id].score,
+                };
+                let line = ron::to_string(&frame).map_err(|error| error.to_string())?;
+                writeln!(writer, "{}", line).map_err(|error| error.to_string())?;
+            }
+        }
+
+        if out_of_energy.iter().all(|done| *done) {
+            println!("Every robot ran out of energy at tick {}", tick);
+            break;
+        }
+    }
+
+    if let Some(mut writer) = replay {
+        writer.flush().map_err(|error| error.to_string())?;
+    }
+
+    println!("Finished in {:.2?}", start.elapsed());
+    for (robot_state, summary) in world_res.robots.iter().zip(summaries.iter()) {
+        println!(
+            "{}: ticks {}, score {:.2}, garbage collected {}, tiles discovered {}, energy used {}",
+            robot_state.name,
+            summary.ticks,
+            summary.score,
+            summary.garbage_collected,
+            summary.tiles_discovered.len(),
+            summary.energy_used,
+        );
+    }
+
+    Ok(())
+}
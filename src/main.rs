@@ -22,14 +22,20 @@ mod world;
 mod resources;
 mod main_menu;
 mod UI;
+mod cli;
+mod config;
+mod headless;
+mod tools;
 pub mod AI;
 
 use robotics_lib::world::world_generator;
 use player::PlayerPlugin;
 use crate::world::resources::WorldRes;
+use crate::world::resources::WorldSettings;
+use crate::config::RobotsConfig;
+use crate::world::themes::{themes_dir, Themes};
 
 use robotics_lib::runner::Runner;
-use robotics_lib::interface::Tools;
 use robotics_lib::runner::Robot;
 use robotics_lib::world::tile::Tile;
 use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
@@ -40,8 +46,6 @@ use std::thread;
 use std::sync::Mutex;
 use crate::AI::training::generate_generator;
 
-const WORLD_SIZE : usize = 300;
-
 #[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
 pub enum AppState {
     #[default]
@@ -51,42 +55,54 @@ pub enum AppState {
 }
 
 fn main() {
-    
-    // Creating the channel from the Runner to the ECS
-    let (tx, rx) = mpsc::channel::<((Vec<Vec<Option<Tile>>>, (usize, usize)), EnvironmentalConditions, f32)>();
-    // Creating an empty world resource
-    let wr = WorldRes {
-        world: None,
-        rx: Mutex::new(rx),
-        player_x: 0,
-        player_y: 0,
-        world_size: WORLD_SIZE,
-        environmental_conditions: EnvironmentalConditions::new(&vec![WeatherType::Sunny], 0, 0).unwrap(), // Just as tmp
-        score: 0.0,
-        elevation: 0,
+    // The world is generated only when Play is pressed, here we just read the settings
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(cli::CliError::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(cli::CliError::Invalid(message)) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
     };
+    let config = options.config;
+
+    // No window, just the Runners
+    if let Some(headless_options) = options.headless {
+        if let Err(message) = headless::run(&config, &headless_options) {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    // Creating the robot and the Runner
-    let r = MyRobot::new(Robot::new(), Mutex::new(tx));
-    struct Tool;
-    impl Tools for Tool {}
-    let tools = vec![Tool];
-    let mut generator = WorldGenerator::init(WORLD_SIZE);
-    let mut generator = generate_generator(421);
-    let run = Runner::new(Box::new(r), &mut generator).unwrap(); // TODO: link tools
+    let key_bindings = config.keys.bindings().expect("Checked by validate");
+    let themes = match Themes::load(&themes_dir(), &config.render.theme) {
+        Ok(themes) => themes,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    };
 
     App::new()
         // Resources 
-        .insert_resource(wr) // The World 
-        .insert_non_send_resource(run) // The Runner, which cannot be passed in a thread safe way
+        .insert_resource(config.world.clone()) // Seed, size and contents of the world to generate
+        .insert_resource(config.simulation.clone()) // Tick durations
+        .insert_resource(config.render.clone()) // Camera speed and zoom
+        .insert_resource(key_bindings)
+        .insert_resource(themes) // Tile and content atlases
+        .insert_resource(RobotsConfig(config.robots.clone())) // Name, brain and color of every robot
         // States 
         .add_state::<AppState>()
         // Plugins
         .add_plugins(DefaultPlugins
             .set(ImagePlugin::default_nearest())         // Clear Pixels
             .set(LogPlugin {
-                    filter: "mygame=debug".into(),       // Less log spam 
-                    level: bevy::log::Level::ERROR,
+                    filter: config.log.filter.clone(),   // Less log spam 
+                    level: config.log.level(),
                 })
             .set(WindowPlugin {
                     primary_window: Some(Window {
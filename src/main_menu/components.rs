@@ -8,3 +8,37 @@ pub struct PlayButton{}
 
 #[derive(Component)]
 pub struct QuitButton{}
+
+#[derive(Component)]
+pub struct SeedButton{}
+
+#[derive(Component)]
+pub struct SeedText{}
+
+#[derive(Component)]
+pub struct SizeButton{}
+
+#[derive(Component)]
+pub struct SizeText{}
+
+#[derive(Component)]
+pub struct PresetButton{}
+
+#[derive(Component)]
+pub struct PresetText{}
+
+// Switches to the next tile theme, in the bottom bar of the game
+#[derive(Component)]
+pub struct ThemeButton{}
+
+// Picks a theme from the list in the main menu, index in Themes::atlases
+#[derive(Component)]
+pub struct ThemeChoiceButton{
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct GeneratorButton{}
+
+#[derive(Component)]
+pub struct GeneratorText{}
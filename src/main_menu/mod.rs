@@ -3,9 +3,10 @@ use crate::AppState;
 use systems::layout::*;
 use crate::main_menu::systems::interactions::*;
 
-mod components;
+// The theme button is also used by the bottom bar of the game
+pub mod components;
 mod styles;
-mod systems;
+pub mod systems;
 
 pub struct MainMenuPlugin;
 
@@ -17,6 +18,13 @@ impl Plugin for MainMenuPlugin {
                 (
                     interact_with_play_button,
                     interact_with_quit_button,
+                    interact_with_generator_button,
+                    interact_with_seed_button,
+                    edit_seed,
+                    interact_with_size_button,
+                    interact_with_preset_button,
+                    interact_with_theme_choice_buttons,
+                    update_settings_text,
                 ).run_if(in_state(AppState::MainMenu))
             );
     }
@@ -21,3 +21,38 @@ pub fn get_button_text_style(asset_server: &Res<AssetServer>) -> TextStyle {
     }
 }
 
+pub const SETTING_BUTTON_STYLE: Style = {
+    let mut style = Style::DEFAULT;
+    style.width = Val::Px(300.0);
+    style.height = Val::Px(50.0);
+    style.justify_content = JustifyContent::Center;
+    style.align_items = AlignItems::Center;
+    style
+};
+
+// Shares the width of a setting button with the other themes
+pub const THEME_CHOICE_STYLE: Style = {
+    let mut style = Style::DEFAULT;
+    style.flex_grow = 1.0;
+    style.height = Val::Percent(100.0);
+    style.justify_content = JustifyContent::Center;
+    style.align_items = AlignItems::Center;
+    style
+};
+
+// The theme in use stays pressed
+pub fn theme_choice_color(current: bool, interaction: Interaction) -> Color {
+    match (current, interaction) {
+        (true, _) | (_, Interaction::Pressed) => PRESSED_BUTTON_COLOR,
+        (false, Interaction::Hovered) => HOVERED_BUTTON_COLOR,
+        (false, Interaction::None) => NORMAL_BUTTON_COLOR,
+    }
+}
+
+pub fn get_setting_text_style(asset_server: &Res<AssetServer>) -> TextStyle {
+    TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 22.0,
+        color: Color::WHITE,
+    }
+}
@@ -2,6 +2,9 @@ use bevy::prelude::*;
 
 use crate::main_menu::components::*;
 use crate::main_menu::styles::*;
+use crate::main_menu::systems::layout::{generator_label, seed_label, size_label, preset_label};
+use crate::world::resources::{GeneratorKind, Seed, WorldSettings, WORLD_SIZES};
+use crate::world::themes::Themes;
 use crate::AppState;
 use bevy::app::AppExit;
 
@@ -50,3 +53,205 @@ pub fn interact_with_quit_button(
         }
     }
 }
+
+pub fn interact_with_generator_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<GeneratorButton>)
+        >,
+    mut world_settings: ResMut<WorldSettings>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                let mut generator = world_settings.generator.next();
+                // The file generator can only be picked if a world file was given
+                if generator == GeneratorKind::File && world_settings.world_file.is_none() {
+                    generator = generator.next();
+                }
+                world_settings.generator = generator;
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_seed_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<SeedButton>)
+        >,
+    mut world_settings: ResMut<WorldSettings>,
+    // The last fixed seed, restored when switching back from random
+    mut last_seed: Local<Option<u64>>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                world_settings.seed = match world_settings.seed {
+                    Seed::Fixed(seed) => {
+                        *last_seed = Some(seed);
+                        Seed::Random
+                    },
+                    Seed::Random => Seed::Fixed(last_seed.unwrap_or(421)),
+                };
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+// Typing digits in the main menu edits the seed, backspace deletes the last digit
+pub fn edit_seed(
+    mut char_events: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut world_settings: ResMut<WorldSettings>,
+) {
+    for event in char_events.iter() {
+        if let Some(digit) = event.char.to_digit(10) {
+            let seed = match world_settings.seed {
+                Seed::Fixed(seed) => seed.checked_mul(10).and_then(|s| s.checked_add(digit as u64)),
+                Seed::Random => Some(digit as u64),
+            };
+            // Ignore the digit if the seed would overflow
+            if let Some(seed) = seed {
+                world_settings.seed = Seed::Fixed(seed);
+            }
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        if let Seed::Fixed(seed) = world_settings.seed {
+            world_settings.seed = Seed::Fixed(seed / 10);
+        }
+    }
+}
+
+pub fn interact_with_size_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<SizeButton>)
+        >,
+    mut world_settings: ResMut<WorldSettings>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                // Next bigger size, wrapping around to the smallest one
+                world_settings.world_size = WORLD_SIZES
+                    .iter()
+                    .copied()
+                    .find(|size| *size > world_settings.world_size)
+                    .unwrap_or(WORLD_SIZES[0]);
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_preset_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<PresetButton>)
+        >,
+    mut world_settings: ResMut<WorldSettings>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                world_settings.content_preset = world_settings.content_preset.next();
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+// Registered by the game UI, the main menu lists the themes instead
+pub fn interact_with_theme_button(
+    mut button_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ThemeButton>)
+        >,
+    mut themes: ResMut<Themes>,
+) {
+    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *background_color = PRESSED_BUTTON_COLOR.into();
+                themes.next();
+            },
+            Interaction::Hovered => {
+                *background_color = HOVERED_BUTTON_COLOR.into();
+            },
+            Interaction::None => {
+                *background_color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+    }
+}
+
+pub fn interact_with_theme_choice_buttons(
+    mut button_query: Query<(&Interaction, &mut BackgroundColor, &ThemeChoiceButton)>,
+    mut themes: ResMut<Themes>,
+) {
+    for (interaction, _, choice) in button_query.iter() {
+        if *interaction == Interaction::Pressed && themes.current != choice.index {
+            themes.current = choice.index;
+        }
+    }
+    // Every button is recolored, the one that was in use isn't the one clicked
+    for (interaction, mut background_color, choice) in button_query.iter_mut() {
+        *background_color = theme_choice_color(choice.index == themes.current, *interaction).into();
+    }
+}
+
+pub fn update_settings_text(
+    world_settings: Res<WorldSettings>,
+    mut set: ParamSet<(
+        Query<&mut Text, With<SeedText>>,
+        Query<&mut Text, With<SizeText>>,
+        Query<&mut Text, With<PresetText>>,
+        Query<&mut Text, With<GeneratorText>>,
+    )>,
+) {
+    if !world_settings.is_changed() {
+        return;
+    }
+
+    if let Ok(mut old_text) = set.p0().get_single_mut() {
+        old_text.sections[0].value = seed_label(&world_settings.seed);
+    }
+    if let Ok(mut old_text) = set.p1().get_single_mut() {
+        old_text.sections[0].value = size_label(world_settings.world_size);
+    }
+    if let Ok(mut old_text) = set.p2().get_single_mut() {
+        old_text.sections[0].value = preset_label(&world_settings);
+    }
+    if let Ok(mut old_text) = set.p3().get_single_mut() {
+        old_text.sections[0].value = generator_label(&world_settings);
+    }
+}
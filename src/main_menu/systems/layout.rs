@@ -1,12 +1,16 @@
 use bevy::prelude::*;
 use crate::main_menu::components::*;
 use crate::main_menu::styles::*;
+use crate::world::resources::{Seed, WorldSettings};
+use crate::world::themes::Themes;
 
 pub fn spawn_main_menu(
     mut commands: Commands,
-    asset_server: Res<AssetServer>
+    asset_server: Res<AssetServer>,
+    world_settings: Res<WorldSettings>,
+    themes: Res<Themes>,
 ) {
-    let main_menu_entity = build_main_menu(&mut commands, &asset_server);
+    let main_menu_entity = build_main_menu(&mut commands, &asset_server, &world_settings, &themes);
     
 
 }
@@ -20,9 +24,30 @@ pub fn despawn_main_menu(
     }
 }
 
+pub fn generator_label(world_settings: &WorldSettings) -> String {
+    format!("Generator: {}", world_settings.generator.name())
+}
+
+pub fn seed_label(seed: &Seed) -> String {
+    match seed {
+        Seed::Random => "Seed: random".to_string(),
+        Seed::Fixed(seed) => format!("Seed: {}", seed),
+    }
+}
+
+pub fn size_label(world_size: usize) -> String {
+    format!("Size: {}x{}", world_size, world_size)
+}
+
+pub fn preset_label(world_settings: &WorldSettings) -> String {
+    format!("Contents: {}", world_settings.content_preset.name())
+}
+
 pub fn build_main_menu(
     commands: &mut Commands,
-    asset_server: &Res<AssetServer>
+    asset_server: &Res<AssetServer>,
+    world_settings: &WorldSettings,
+    themes: &Themes,
 ) -> Entity {
     let main_menu_entity = commands.spawn(
         (
@@ -88,6 +113,181 @@ pub fn build_main_menu(
         });
 
 
+        // Generator Button
+        parent.spawn(
+            (
+                ButtonBundle{
+                    style: SETTING_BUTTON_STYLE,
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                },
+                GeneratorButton{},
+            )
+        )
+        .with_children(|parent| {
+            // Text 
+            parent.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection::new(
+                                generator_label(world_settings),
+                                get_setting_text_style(asset_server),
+                            )
+                        ],
+                        alignment: TextAlignment::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                GeneratorText{},
+            ));
+        });
+
+
+        // Seed Button, click to switch between random and fixed, type to edit
+        parent.spawn(
+            (
+                ButtonBundle{
+                    style: SETTING_BUTTON_STYLE,
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                },
+                SeedButton{},
+            )
+        )
+        .with_children(|parent| {
+            // Text 
+            parent.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection::new(
+                                seed_label(&world_settings.seed),
+                                get_setting_text_style(asset_server),
+                            )
+                        ],
+                        alignment: TextAlignment::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                SeedText{},
+            ));
+        });
+
+
+        // Size Button
+        parent.spawn(
+            (
+                ButtonBundle{
+                    style: SETTING_BUTTON_STYLE,
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                },
+                SizeButton{},
+            )
+        )
+        .with_children(|parent| {
+            // Text 
+            parent.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection::new(
+                                size_label(world_settings.world_size),
+                                get_setting_text_style(asset_server),
+                            )
+                        ],
+                        alignment: TextAlignment::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                SizeText{},
+            ));
+        });
+
+
+        // Content Preset Button
+        parent.spawn(
+            (
+                ButtonBundle{
+                    style: SETTING_BUTTON_STYLE,
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                },
+                PresetButton{},
+            )
+        )
+        .with_children(|parent| {
+            // Text 
+            parent.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection::new(
+                                preset_label(world_settings),
+                                get_setting_text_style(asset_server),
+                            )
+                        ],
+                        alignment: TextAlignment::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                PresetText{},
+            ));
+        });
+
+
+        // Theme list, one button per theme, the one in use is highlighted
+        parent.spawn(
+            NodeBundle {
+                style: Style {
+                    width: Val::Px(300.0),
+                    height: Val::Px(50.0),
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            }
+        )
+        .with_children(|parent| {
+            for (index, atlas) in themes.atlases.iter().enumerate() {
+                parent.spawn(
+                    (
+                        ButtonBundle{
+                            style: THEME_CHOICE_STYLE,
+                            background_color: theme_choice_color(index == themes.current, Interaction::None).into(),
+                            ..default()
+                        },
+                        ThemeChoiceButton{ index },
+                    )
+                )
+                .with_children(|parent| {
+                    // Text 
+                    parent.spawn(
+                        TextBundle {
+                            text: Text {
+                                sections: vec![
+                                    TextSection::new(
+                                        atlas.name.clone(),
+                                        get_setting_text_style(asset_server),
+                                    )
+                                ],
+                                alignment: TextAlignment::Center,
+                                ..default()
+                            },
+                            ..default()
+                        }
+                    );
+                });
+            }
+        });
+
+
         // Quit Button
         parent.spawn(
             (
@@ -2,4 +2,16 @@ use bevy::prelude::*;
 
 // Create the r componet 
 #[derive(Component)]
-pub struct Player{}
+pub struct Player{
+    // Index of the robot in WorldRes::robots and in the Runners
+    pub id: usize,
+}
+
+// The sprite going from one tile to the next, in world coordinates
+#[derive(Component, Default)]
+pub struct Movement {
+    pub from: Vec2,
+    pub to: Vec2,
+    // From 0 when it leaves `from`, to 1 when it gets to `to`
+    pub progress: f32,
+}
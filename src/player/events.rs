@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use robotics_lib::event::events::Event as LibEvent;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+
+// The groups used to filter the event log
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventCategory {
+    Lifecycle,
+    Time,
+    Energy,
+    Movement,
+    Tiles,
+    Backpack,
+    Tools,
+}
+
+impl EventCategory {
+    pub const ALL: [EventCategory; 7] = [
+        EventCategory::Lifecycle,
+        EventCategory::Time,
+        EventCategory::Energy,
+        EventCategory::Movement,
+        EventCategory::Tiles,
+        EventCategory::Backpack,
+        EventCategory::Tools,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventCategory::Lifecycle => "Run",
+            EventCategory::Time => "Time",
+            EventCategory::Energy => "Energy",
+            EventCategory::Movement => "Move",
+            EventCategory::Tiles => "Tiles",
+            EventCategory::Backpack => "Backpack",
+            EventCategory::Tools => "Tools",
+        }
+    }
+}
+
+// A robotics_lib Event, owned so it can be sent from the Runner to the ECS.
+// ToolUsed and ToolFailed are ours, sent when the robot uses a tool,
+// NotEnoughEnergy too, sent when the action of the tick could not be paid
+#[derive(Clone, Debug)]
+pub enum RobotEventKind {
+    Ready,
+    Terminated,
+    TimeChanged(EnvironmentalConditions),
+    DayChanged(EnvironmentalConditions),
+    EnergyRecharged(usize),
+    EnergyConsumed(usize),
+    Moved(Tile, (usize, usize)),
+    TileContentUpdated(Tile, (usize, usize)),
+    AddedToBackpack(Content, usize),
+    RemovedFromBackpack(Content, usize),
+    // Name of the tool and energy it used
+    ToolUsed(&'static str, usize),
+    // Name of the tool and why it failed
+    ToolFailed(&'static str, String),
+    // Name of the action the robot could not afford
+    NotEnoughEnergy(&'static str),
+}
+
+impl From<LibEvent> for RobotEventKind {
+    fn from(event: LibEvent) -> Self {
+        match event {
+            LibEvent::Ready => RobotEventKind::Ready,
+            LibEvent::Terminated => RobotEventKind::Terminated,
+            LibEvent::TimeChanged(conditions) => RobotEventKind::TimeChanged(conditions),
+            LibEvent::DayChanged(conditions) => RobotEventKind::DayChanged(conditions),
+            LibEvent::EnergyRecharged(amount) => RobotEventKind::EnergyRecharged(amount),
+            LibEvent::EnergyConsumed(amount) => RobotEventKind::EnergyConsumed(amount),
+            LibEvent::Moved(tile, position) => RobotEventKind::Moved(tile, position),
+            LibEvent::TileContentUpdated(tile, position) => RobotEventKind::TileContentUpdated(tile, position),
+            LibEvent::AddedToBackpack(content, quantity) => RobotEventKind::AddedToBackpack(content, quantity),
+            LibEvent::RemovedFromBackpack(content, quantity) => RobotEventKind::RemovedFromBackpack(content, quantity),
+        }
+    }
+}
+
+impl RobotEventKind {
+    pub fn category(&self) -> EventCategory {
+        match self {
+            RobotEventKind::Ready | RobotEventKind::Terminated => EventCategory::Lifecycle,
+            RobotEventKind::TimeChanged(_) | RobotEventKind::DayChanged(_) => EventCategory::Time,
+            RobotEventKind::EnergyRecharged(_)
+            | RobotEventKind::EnergyConsumed(_)
+            | RobotEventKind::NotEnoughEnergy(_) => EventCategory::Energy,
+            RobotEventKind::Moved(_, _) => EventCategory::Movement,
+            RobotEventKind::TileContentUpdated(_, _) => EventCategory::Tiles,
+            RobotEventKind::AddedToBackpack(_, _) | RobotEventKind::RemovedFromBackpack(_, _) => EventCategory::Backpack,
+            RobotEventKind::ToolUsed(_, _) | RobotEventKind::ToolFailed(_, _) => EventCategory::Tools,
+        }
+    }
+
+    // One line for the event log
+    pub fn describe(&self) -> String {
+        match self {
+            RobotEventKind::Ready => "ready".to_string(),
+            RobotEventKind::Terminated => "terminated".to_string(),
+            RobotEventKind::TimeChanged(conditions) => format!(
+                "time {} {:?}",
+                conditions.get_time_of_day_string(),
+                conditions.get_weather_condition()
+            ),
+            RobotEventKind::DayChanged(conditions) => format!(
+                "new day, {:?}",
+                conditions.get_weather_condition()
+            ),
+            RobotEventKind::EnergyRecharged(amount) => format!("energy +{}", amount),
+            RobotEventKind::EnergyConsumed(amount) => format!("energy -{}", amount),
+            RobotEventKind::Moved(tile, (x, y)) => format!("moved to ({}, {}) {:?}", x, y, tile.tile_type),
+            RobotEventKind::TileContentUpdated(tile, (x, y)) => format!("tile ({}, {}) now {:?}", x, y, tile.content),
+            RobotEventKind::AddedToBackpack(content, quantity) => format!("backpack +{} {:?}", quantity, content),
+            RobotEventKind::RemovedFromBackpack(content, quantity) => format!("backpack -{} {:?}", quantity, content),
+            RobotEventKind::ToolUsed(tool, energy) => format!("used {}, energy -{}", tool, energy),
+            RobotEventKind::ToolFailed(tool, error) => format!("{} failed: {}", tool, error),
+            RobotEventKind::NotEnoughEnergy(action) => format!("not enough energy to {}", action),
+        }
+    }
+}
+
+// Sent by the ECS for every event of every robot, any system can read them with EventReader<RobotEvent>
+#[derive(Event, Clone, Debug)]
+pub struct RobotEvent {
+    // Index of the robot in WorldRes::robots
+    pub robot: usize,
+    // The tick of the robot when the event happened
+    pub tick: usize,
+    pub kind: RobotEventKind,
+}
+
+// Sent by the UI to one robot, which runs it at its next tick instead of asking its brain
+#[derive(Clone, Debug)]
+pub enum RobotCommand {
+    // Name of the tool, as in ToolConfig::name
+    UseTool(&'static str),
+}
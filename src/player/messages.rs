@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+use crate::AI::planning::Plan;
+
+// Robot coordinates: (row, col), row grows downwards
+pub type Coords = (usize, usize);
+
+// What a robot tells the ECS. Every producer (MyRobot) and consumer (update_map, headless)
+// goes through this, each message only carries what changed
+#[derive(Clone, Debug)]
+pub enum SimMessage {
+    // Tiles the robot learned about, or that changed since it last saw them
+    TilesRevealed(Vec<(Coords, Tile)>),
+    // New position of the robot and the elevation of the tile under it
+    RobotMoved(Coords, usize),
+    EnergyChanged(usize),
+    // The whole backpack, it's small
+    BackpackChanged(HashMap<Content, usize>),
+    WeatherChanged(EnvironmentalConditions),
+    ScoreChanged(f32),
+    // The tiles the robot sees right now, around itself
+    InView(Vec<Coords>),
+    // What the robot is going for and how, None when it has no idea
+    PlanChanged(Option<Plan>),
+    // Sent last, with the tick that just ended
+    TickCompleted(usize),
+}
+
+// Messages sent by a robot and not read yet, std channels can't tell their length.
+// The robot and the ECS share the same counter
+#[derive(Clone, Default, Debug)]
+pub struct QueueDepth(Arc<AtomicUsize>);
+
+impl QueueDepth {
+    pub fn sent(&self, count: usize) {
+        self.0.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn received(&self, count: usize) {
+        self.0.fetch_sub(count, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
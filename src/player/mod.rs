@@ -7,25 +7,36 @@ use crate::player::systems::change_tick_speed;
 use crate::AppState;
 use crate::player::systems::pause_tick;
 use crate::player::systems::unpause_tick;
+use crate::player::systems::spawn_runner;
+use crate::player::systems::forward_robot_events;
+use crate::player::systems::draw_robot_plans;
+use crate::player::events::RobotEvent;
 
 pub mod components;
 pub mod systems;
 pub mod resources;
+pub mod events;
+pub mod messages;
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app
+            .add_event::<RobotEvent>()
             // Systems 
+            // The Runner is built when leaving the main menu, so before anything in OnEnter(Game)
+            .add_systems(OnExit(AppState::MainMenu), spawn_runner)
             .add_systems(OnEnter(AppState::Game), spawn_player)
             .add_systems(Update, 
                 (
                     tick_loop.after(update_map),
                     move_player.after(tick_loop),
+                    forward_robot_events.after(tick_loop),
                     change_tick_speed,
                     pause_tick,
-                    unpause_tick
+                    unpause_tick,
+                    draw_robot_plans
                 )
                 .run_if(in_state(AppState::Game))
              );
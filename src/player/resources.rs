@@ -4,6 +4,7 @@ use robotics_lib::energy::Energy;
 use robotics_lib::event::events::Event;
 use robotics_lib::runner::Runnable;
 use robotics_lib::runner::Robot;
+use robotics_lib::runner::Runner;
 use robotics_lib::world::World;
 use robotics_lib::interface::{Direction, put};
 use robotics_lib::interface::go;
@@ -12,7 +13,7 @@ use bevy::ecs::system::Resource;
 use crate::{AI, Component};
 use robotics_lib::world::tile::{Content, Tile};
 use robotics_lib::interface::robot_map;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Mutex;
 use rand::Rng;
 use crate::Timer;
@@ -21,7 +22,8 @@ use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
 use robotics_lib::interface::look_at_sky;
 use robotics_lib::interface::get_score;
 use robotics_lib::interface::destroy;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
 use robotics_lib::utils::LibError;
 use robotics_lib::interface::discover_tiles;
 use robotics_lib::interface::one_direction_view;
@@ -29,24 +31,148 @@ use std::time::Duration;
 use std::thread;
 use op_map::op_pathfinding::{get_best_action_to_element, OpActionInput, OpActionOutput, ShoppingList};
 use op_map::op_pathfinding::OpActionInput::Destroy;
-use rust_eze_spotlight::Spotlight;
 use AI::training;
 use crate::AI::training::{Brain, train};
 use crate::AI::training::BrainAction;
 use crate::AI::training::BrainAction::*;
+use crate::player::events::{RobotCommand, RobotEventKind};
+use crate::player::messages::{Coords, QueueDepth, SimMessage};
+use crate::AI::planning::{plan_route, Plan};
+use crate::tools::ToolRegistry;
+use crate::config::RobotConfig;
+use crate::world::generator::WorldGenerator;
+use crate::world::resources::{RobotState, WorldRes, WorldSettings};
+use robotics_lib::world::environmental_conditions::WeatherType;
+use std::sync::mpsc;
+
+// Every this many ticks the whole robot_map is compared with what was sent,
+// in case a tile changed outside of the areas the robot looked at
+const RESYNC_TICKS: usize = 500;
 
 #[derive(Resource)]
 pub struct TickTimer {
     pub timer: Timer,
 }
 
+// One Runner per robot, all on the same seed. Like the Runner, it's a non send resource
+pub struct Runners(pub Vec<Runner>);
+
+// Builds one Runner per robot and the WorldRes that receives their updates.
+// Every robot gets its own Runner on the same seed, so they explore identical worlds
+pub fn build_runners(settings: &WorldSettings, robots_config: &[RobotConfig], seed: u64) -> Result<(Runners, WorldRes), String> {
+    let mut world_size = settings.world_size;
+    let mut robots = Vec::with_capacity(robots_config.len());
+    let mut runners = Vec::with_capacity(robots_config.len());
+
+    for robot_config in robots_config {
+        let mut generator = WorldGenerator::from_settings(settings, seed)?;
+        world_size = generator.world_size(settings);
+        let brain = Brain::from_config(&robot_config.brain)
+            .map_err(|error| format!("cannot create the brain of {}: {}", robot_config.name, error))?;
+
+        // Creating the channel from the Runner to the ECS
+        let (tx, rx) = mpsc::channel::<SimMessage>();
+        let queue_depth = QueueDepth::default();
+        let (events_tx, events_rx) = mpsc::channel::<(usize, RobotEventKind)>();
+        // And the one from the ECS to the robot
+        let (commands_tx, commands_rx) = mpsc::channel::<RobotCommand>();
+
+        // Creating the robot and the Runner
+        let tools = ToolRegistry::new(&robot_config.tools);
+        let r = MyRobot::new(Robot::new(), Mutex::new(tx), Mutex::new(events_tx), Mutex::new(commands_rx), queue_depth.clone(), brain, tools);
+        let run = Runner::new(Box::new(r), &mut generator)
+            .map_err(|error| format!("cannot create the Runner of {}: {:?}", robot_config.name, error))?;
+
+        robots.push(RobotState {
+            name: robot_config.name.clone(),
+            color: robot_config.color(),
+            rx: Mutex::new(rx),
+            queue_depth,
+            peak_drained: 0,
+            events_rx: Mutex::new(events_rx),
+            commands_tx: Mutex::new(commands_tx),
+            player_x: 0,
+            player_y: 0,
+            score: 0.0,
+            elevation: 0,
+            energy: 0,
+            backpack: HashMap::new(),
+            in_view: Vec::new(),
+            plan: None,
+            trail: VecDeque::new(),
+        });
+        runners.push(run);
+    }
+
+    // Creating an empty world resource
+    let wr = WorldRes::new(
+        world_size,
+        EnvironmentalConditions::new(&vec![WeatherType::Sunny], 0, 0).unwrap(), // Just as tmp
+        robots,
+    );
+
+    Ok((Runners(runners), wr))
+}
+
 
-pub struct MyRobot(pub Robot, pub Mutex<Sender<((Vec<Vec<Option<Tile>>>, (usize, usize)), EnvironmentalConditions, f32)>>, Brain);
+pub struct MyRobot {
+    pub robot: Robot,
+    // Everything the ECS needs to draw
+    pub tx: Mutex<Sender<SimMessage>>,
+    // Shared with the ECS, counts what was sent on tx and not read yet
+    pub queue_depth: QueueDepth,
+    // Every robotics_lib event, with the tick it happened in
+    pub events_tx: Mutex<Sender<(usize, RobotEventKind)>>,
+    // Commands from the UI
+    pub commands_rx: Mutex<Receiver<RobotCommand>>,
+    brain: Brain,
+    tools: ToolRegistry,
+    tick: usize,
+    // The last decision of the brain, its route is sent with the other changes of the tick
+    decision: Option<BrainAction>,
+    // Tiles outside of the 3x3 view that may have changed during the tick, read back from
+    // robot_map at the end of it
+    dirty: HashSet<Coords>,
+    // What the ECS already knows, to send only what changed
+    last_sent: LastSent,
+}
+
+#[derive(Default)]
+struct LastSent {
+    // Same as robot_map, kept up to date with the tiles sent
+    map: Vec<Vec<Option<Tile>>>,
+    position: Option<Coords>,
+    energy: Option<usize>,
+    backpack: HashMap<Content, usize>,
+    score: Option<f32>,
+    plan: Option<Plan>,
+    // The decision the plan was computed for, it's only computed again when something changes
+    planned_for: Option<BrainAction>,
+}
 
 impl MyRobot {
-    pub(crate) fn new(r: Robot, m: Mutex<Sender<((Vec<Vec<Option<Tile>>>, (usize, usize)), EnvironmentalConditions, f32)>>) -> Self {
-        // Self(r, m, Brain::from_network(net))
-        Self(r, m, Brain::default())
+    pub(crate) fn new(
+        r: Robot,
+        m: Mutex<Sender<SimMessage>>,
+        events: Mutex<Sender<(usize, RobotEventKind)>>,
+        commands: Mutex<Receiver<RobotCommand>>,
+        queue_depth: QueueDepth,
+        brain: Brain,
+        tools: ToolRegistry,
+    ) -> Self {
+        Self {
+            robot: r,
+            tx: m,
+            events_tx: events,
+            commands_rx: commands,
+            queue_depth,
+            brain,
+            tools,
+            tick: 0,
+            decision: None,
+            dirty: HashSet::new(),
+            last_sent: LastSent::default(),
+        }
     }
 }
 
@@ -55,116 +181,291 @@ impl Runnable for MyRobot {
         // println!("Tick nel roboto vero");
         // let niugy = robot_map(world).expect("Errore nella mappa");
 
-        let brain_action = self.2.think_action(world, self);
-
-        match brain_action {
-            None => {}
-            Some(opa) => {
-                match opa {
-                    OpActionOutput::Move(dir) => {
-                        self.go_ui(world, dir);
-                    }
-                    OpActionOutput::Destroy(dir) => {
-                        destroy(self, world, dir);
-                    }
-                    OpActionOutput::Put(content, quantity, dir) => {
-                        put(self, world, content, quantity, dir);
-                    }
-                }
-            }
-        }
+        self.tick += 1;
+        self.act(world);
+        // Whatever the robot did, the screen has to match what it knows
+        self.send_changes_ui(world);
     }
 
     fn handle_event(&mut self, event: Event) {
-        // println!("{:?}", event);
+        if let Event::TimeChanged(conditions) | Event::DayChanged(conditions) = &event {
+            self.queue_depth.sent(1);
+            let _ = self.tx.lock().unwrap().send(SimMessage::WeatherChanged(conditions.clone()));
+        }
+        // Forwarded to the ECS, which shows it in the event log
+        let _ = self.events_tx.lock().unwrap().send((self.tick, RobotEventKind::from(event)));
     }
 
     fn get_energy(&self) -> &Energy {
-        &self.0.energy
+        &self.robot.energy
     }
     fn get_energy_mut(&mut self) -> &mut Energy {
-        &mut self.0.energy
+        &mut self.robot.energy
     }
 
     fn get_coordinate(&self) -> &Coordinate {
-        &self.0.coordinate
+        &self.robot.coordinate
     }
     fn get_coordinate_mut(&mut self) -> &mut Coordinate {
-        &mut self.0.coordinate
+        &mut self.robot.coordinate
     }
 
     fn get_backpack(&self) -> &BackPack {
-        &self.0.backpack
+        &self.robot.backpack
     }
     fn get_backpack_mut(&mut self) -> &mut BackPack {
-        &mut self.0.backpack
+        &mut self.robot.backpack
     }
 }
 
 impl MyRobot {
-    fn go_ui(&mut self, world: &mut World, direction: Direction) {
-        let _ = go(self, world, direction);
-        let view = where_am_i(self, world);
-        let condition = look_at_sky(world);
-        let score = get_score(world);
-        let _ = self.1.lock().unwrap().send((view, condition, score));
-    }
+    // Does what the user or the brain want for this tick
+    fn act(&mut self, world: &mut World) {
+        // What the user asked for comes first, and takes the whole tick
+        let command = self.commands_rx.lock().unwrap().try_recv();
+        if let Ok(RobotCommand::UseTool(name)) = command {
+            match self.tools.find(name) {
+                Some(index) => self.use_tool_ui(world, index),
+                None => {
+                    let kind = RobotEventKind::ToolFailed(name, "this robot doesn't have it".to_string());
+                    let _ = self.events_tx.lock().unwrap().send((self.tick, kind));
+                }
+            }
+            return;
+        }
 
-    fn discover_tiles_ui(&mut self, world: &mut World, to_discover: &[(usize, usize)]) -> Result<HashMap<(usize, usize), Option<Tile>>, LibError> {
-        let discovered_hash = discover_tiles(self, world, &to_discover)?;
-        let mut empty_vec: Vec<Vec<Option<Tile>>> = vec![vec![None, None, None], vec![None, None, None], vec![None, None, None]];
+        let decision = self.brain.decide(world, self);
 
-        let condition = look_at_sky(world);
-        let score = get_score(world);
+        // Nothing useful is known: a tool may show more of the world than walking around
+        if let Explore() = decision {
+            let energy = self.get_energy().get_energy_level();
+            if let Some(index) = self.tools.ready(self.tick, energy) {
+                self.use_tool_ui(world, index);
+                return;
+            }
+            // Looking far away is cheaper, and the path is chosen with what it shows
+            if let Some((direction, distance)) = self.brain.scan_direction(self, self.tick) {
+                let _ = self.one_direction_view_ui(world, direction, distance);
+            }
+        }
+
+        self.decision = Some(decision.clone());
+        let brain_action = self.brain.action_for(world, self, decision);
+
+        let result = match brain_action {
+            None => return,
+            Some(opa) => {
+                match opa {
+                    OpActionOutput::Move(dir) => {
+                        ("go", self.go_ui(world, dir))
+                    }
+                    OpActionOutput::Destroy(dir) => {
+                        ("destroy", destroy(self, world, dir).map(|_| ()))
+                    }
+                    OpActionOutput::Put(content, quantity, dir) => {
+                        ("put", put(self, world, content, quantity, dir).map(|_| ()))
+                    }
+                }
+            }
+        };
 
-        for (x, y) in to_discover {
-            empty_vec[1][1] = discovered_hash.get(&(*x, *y)).unwrap().clone();
-            let view = (empty_vec.clone(), (*x, *y));
-            let _ = self.1.lock().unwrap().send((view, condition.clone(), score));
+        // The headless run stops the robots that can't pay for their actions anymore
+        if let (action, Err(LibError::NotEnoughEnergy)) = result {
+            let _ = self.events_tx.lock().unwrap().send((self.tick, RobotEventKind::NotEnoughEnergy(action)));
         }
-        Ok(discovered_hash)
     }
 
-    /*
-    fn one_direction_view_ui(&mut self, world: &World, direction: Direction, distance: usize) -> Result<Vec<Vec<Tile>>, LibError> {
-        
-        if distance <= 0 {
-            Err(LibError::OperationNotAllowed)
-        }
+    fn use_tool_ui(&mut self, world: &mut World, index: usize) {
+        let Some(tool) = self.tools.get(index).cloned() else {
+            return;
+        };
+        self.tools.mark_used(index, self.tick);
 
-        let view = one_direction_view(self, world, direction, distance)?;
-     
-        let condition = look_at_sky(world);
-        let score = get_score(world);
+        let reach = tool.reach();
+        let row = self.get_coordinate().get_row();
+        let col = self.get_coordinate().get_col();
+        self.mark_dirty(row.saturating_sub(reach)..=row + reach, col.saturating_sub(reach)..=col + reach);
 
+        let kind = match tool.invoke(self, world) {
+            Ok(energy) => RobotEventKind::ToolUsed(tool.name(), energy),
+            Err(error) => RobotEventKind::ToolFailed(tool.name(), format!("{:?}", error)),
+        };
+        let _ = self.events_tx.lock().unwrap().send((self.tick, kind));
+    }
 
-        match direction {
-            Direction::Up => {
-                if distance == 1 {
-                    let new_view = vec![view.0, vec![], vec![]];
-                } 
-                else if distance == 2 {
+    // Sends what changed during the tick, then TickCompleted
+    fn send_changes_ui(&mut self, world: &mut World) {
+        let (view, position) = where_am_i(self, world);
+        let mut messages = Vec::new();
 
-                }
-                // Caso generale
-                else {
+        // The first tick has no TimeChanged event, the ECS needs the weather anyway
+        if self.last_sent.position.is_none() {
+            messages.push(SimMessage::WeatherChanged(look_at_sky(world)));
+        }
 
+        // Only the tiles that can have changed are compared with what was sent: the 3x3 view,
+        // which covers moving, destroy and put, and the areas the robot looked at.
+        // robot_map is only read when there's one of those, or for the occasional full resync
+        let resync = self.last_sent.map.is_empty() || self.tick % RESYNC_TICKS == 0;
+        let mut updates: Vec<(Coords, Tile)> = Vec::new();
+        if resync || !self.dirty.is_empty() {
+            if let Some(known) = robot_map(world) {
+                let size = known.len();
+                if self.last_sent.map.len() != size {
+                    self.last_sent.map = vec![vec![None; size]; size];
+                }
+                if resync {
+                    for (x, row) in known.iter().enumerate() {
+                        for (y, tile) in row.iter().enumerate() {
+                            if let Some(tile) = tile {
+                                if self.last_sent.map[x][y].as_ref() != Some(tile) {
+                                    updates.push(((x, y), tile.clone()));
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    for (x, y) in self.dirty.iter().copied().filter(|(x, y)| *x < size && *y < size) {
+                        if let Some(tile) = &known[x][y] {
+                            updates.push(((x, y), tile.clone()));
+                        }
+                    }
                 }
-            },
-            Direction::Down => {
+            }
+        }
+        self.dirty.clear();
+        for (i, row) in view.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if let Some(tile) = tile {
+                    if position.0 + i >= 1 && position.1 + j >= 1 {
+                        updates.push(((position.0 + i - 1, position.1 + j - 1), tile.clone()));
+                    }
+                }
+            }
+        }
+
+        let size = self.last_sent.map.len();
+        let mut changed = Vec::new();
+        for ((x, y), tile) in updates {
+            if x < size && y < size && self.last_sent.map[x][y].as_ref() != Some(&tile) {
+                self.last_sent.map[x][y] = Some(tile.clone());
+                changed.push(((x, y), tile));
+            }
+        }
+
+        // The route to the target of the decision, with the map the robot has after acting.
+        // Searched again only when the decision changes, the robot leaves the route or a tile on it
+        // changes. Without a route, any new tile may open one
+        if let Some(decision) = &self.decision {
+            let replan = self.last_sent.planned_for.as_ref() != Some(decision)
+                || match &self.last_sent.plan {
+                    Some(plan) => {
+                        !plan.path.contains(&position)
+                            || changed.iter().any(|(coords, _)| *coords == plan.target || plan.path.contains(coords))
+                    }
+                    None => !changed.is_empty(),
+                };
+            let plan = if replan {
+                self.last_sent.planned_for = Some(decision.clone());
+                plan_route(&self.last_sent.map, position, decision)
+            } else {
+                // Still on the route, the steps behind the robot are dropped
+                self.last_sent.plan.clone().map(|mut plan| {
+                    let here = plan.path.iter().position(|step| *step == position).expect("Checked by replan");
+                    plan.path.drain(..here);
+                    plan
+                })
+            };
+            if plan != self.last_sent.plan {
+                messages.push(SimMessage::PlanChanged(plan.clone()));
+                self.last_sent.plan = plan;
+            }
+        }
+
+        if !changed.is_empty() {
+            messages.push(SimMessage::TilesRevealed(changed));
+        }
+
+        // Sent every tick, even when the robot doesn't move, it's what keeps the fog away
+        let in_view = view
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| row.iter().enumerate().map(move |(j, tile)| (i, j, tile.is_some())))
+            .filter(|(i, j, known)| *known && position.0 + i >= 1 && position.1 + j >= 1)
+            .map(|(i, j, _)| (position.0 + i - 1, position.1 + j - 1))
+            .collect();
+        messages.push(SimMessage::InView(in_view));
 
-            },
-            Direction::Left => {
+        if self.last_sent.position != Some(position) {
+            let elevation = view[1][1].as_ref().map_or(0, |tile| tile.elevation);
+            messages.push(SimMessage::RobotMoved(position, elevation));
+            self.last_sent.position = Some(position);
+        }
 
-            },
-            Direction::Right => {
+        let energy = self.get_energy().get_energy_level();
+        if self.last_sent.energy != Some(energy) {
+            messages.push(SimMessage::EnergyChanged(energy));
+            self.last_sent.energy = Some(energy);
+        }
 
-            },
+        let backpack = self.get_backpack().get_contents().clone();
+        if self.last_sent.backpack != backpack {
+            messages.push(SimMessage::BackpackChanged(backpack.clone()));
+            self.last_sent.backpack = backpack;
         }
 
+        let score = get_score(world);
+        if self.last_sent.score != Some(score) {
+            messages.push(SimMessage::ScoreChanged(score));
+            self.last_sent.score = Some(score);
+        }
 
+        messages.push(SimMessage::TickCompleted(self.tick));
+        self.queue_depth.sent(messages.len());
+        let tx = self.tx.lock().unwrap();
+        for message in messages {
+            let _ = tx.send(message);
+        }
+    }
 
-        Ok(view)
+    // The new position is sent with the other map changes at the end of the tick
+    fn go_ui(&mut self, world: &mut World, direction: Direction) -> Result<(), LibError> {
+        go(self, world, direction).map(|_| ())
+    }
+
+    fn discover_tiles_ui(&mut self, world: &mut World, to_discover: &[(usize, usize)]) -> Result<HashMap<(usize, usize), Option<Tile>>, LibError> {
+        // The discovered tiles end up in robot_map, they are drawn at the end of the tick
+        self.dirty.extend(to_discover.iter().copied());
+        discover_tiles(self, world, &to_discover)
+    }
+
+    // Looks `distance` tiles away in one direction, the strip is 3 tiles wide
+    fn one_direction_view_ui(&mut self, world: &mut World, direction: Direction, distance: usize) -> Result<Vec<Vec<Tile>>, LibError> {
+        if distance == 0 {
+            return Err(LibError::OperationNotAllowed);
+        }
+
+        // The strip in robot coordinates, drawn with the other map changes at the end of the tick.
+        // The tiles outside of the world are skipped there
+        let row = self.get_coordinate().get_row();
+        let col = self.get_coordinate().get_col();
+        let (rows, cols) = match direction {
+            Direction::Up => (row.saturating_sub(distance)..=row.saturating_sub(1), col.saturating_sub(1)..=col + 1),
+            Direction::Down => (row + 1..=row + distance, col.saturating_sub(1)..=col + 1),
+            Direction::Left => (row.saturating_sub(1)..=row + 1, col.saturating_sub(distance)..=col.saturating_sub(1)),
+            Direction::Right => (row.saturating_sub(1)..=row + 1, col + 1..=col + distance),
+        };
+        self.mark_dirty(rows, cols);
+
+        one_direction_view(self, world, direction, distance)
+    }
+
+    // Tiles robot_map has to be read back for at the end of the tick
+    fn mark_dirty(&mut self, rows: RangeInclusive<usize>, cols: RangeInclusive<usize>) {
+        for x in rows {
+            for y in cols.clone() {
+                self.dirty.insert((x, y));
+            }
+        }
     }
-    */
 }
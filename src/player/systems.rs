@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
-use crate::player::components::Player;
+use crate::player::components::{Movement, Player};
 use robotics_lib::runner::Runnable;
 use robotics_lib::runner::Runner;
 use robotics_lib::interface::Tools;
@@ -10,16 +10,43 @@ use crate::WorldRes;
 use bevy::time::Timer;
 use std::time::Duration;
 use crate::player::resources::TickTimer;
+use crate::player::resources::{build_runners, Runners};
+use crate::world::resources::WorldSettings;
+use crate::config::{KeyBindings, RobotsConfig, SimulationConfig};
+use crate::player::events::RobotEvent;
 use bevy::prelude::Transform;
+use robotics_lib::world::tile::Tile;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::environmental_conditions::WeatherType;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use crate::AI::planning::PlanKind;
+use crate::world::coords::{robot_to_world, TILE_SIZE};
 
 // pub const PLAYER_SIZE : f32 = 32.0;
 
-const FACTOR :f32 = 16.0;
+// Part of a tick spent moving, the sprite stands still on the tile for the rest
+const MOVE_TICK_FRACTION: f32 = 0.8;
 
-// Fine tuded by hand to rowk on a 30x30 world
-const TRANSLATE_Y : f32 = -7.973;
-const TRANSLATE_X : f32 = -7.973;
 
+// Builds the world, the robots and their Runners from the WorldSettings chosen in the main menu.
+// It's an exclusive system because the Runner is not Send, so it has to be
+// inserted as a non send resource
+pub fn spawn_runner(world: &mut World) {
+    let settings = world.resource::<WorldSettings>().clone();
+    let robots_config = world.resource::<RobotsConfig>().0.clone();
+    let seed = settings.seed.resolve();
+    println!(
+        "Generating a world with the {} generator, seed {}, size {} and the {} content preset",
+        settings.generator.name(), seed, settings.world_size, settings.content_preset.name()
+    );
+
+    let (runners, wr) = build_runners(&settings, &robots_config, seed)
+        .unwrap_or_else(|error| panic!("Failed to create the Runners: {}", error));
+
+    world.insert_resource(wr); // The World
+    world.insert_non_send_resource(runners); // The Runners, which cannot be passed in a thread safe way
+}
 
 // This is a system
 pub fn spawn_player(
@@ -32,92 +59,116 @@ pub fn spawn_player(
     // Onlt one Resource<T> of type T can exist at one time 
     asset_server : Res<AssetServer>,
     // The timer for the ticks
+    simulation_config: Res<SimulationConfig>,
+    world_res: Res<WorldRes>,
 ) {
     // Get a reverence to our window 
     // There is onlt one window which is the primary window 
     // so instead of an iterator, we use get_single() 
     let window = window_query.get_single().unwrap();
 
-    commands.spawn(
-        (
-            // We are spawning a SpriteBundle
-            // We use bundles to quickly add or remove sets of Components 
-            // to or form an Entiy 
-            // To know: Do not use bundles as queries! 
-            SpriteBundle {
-                // transform : Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
-                transform : Transform::from_xyz(5.0, 0.0, 2.0),
-                texture: asset_server.load("robot_pixelart.png"), 
-                ..default()
-            },
-            Player{},
-        ),
-    );
+    // One sprite per robot, tinted with the robot color
+    for (id, robot) in world_res.robots.iter().enumerate() {
+        commands.spawn(
+            (
+                // We are spawning a SpriteBundle
+                // We use bundles to quickly add or remove sets of Components 
+                // to or form an Entiy 
+                // To know: Do not use bundles as queries! 
+                SpriteBundle {
+                    // transform : Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
+                    // Later robots are drawn on top of the earlier ones
+                    transform : Transform::from_xyz(5.0, 0.0, 2.0 + id as f32 * 0.01),
+                    texture: asset_server.load("robot_pixelart.png"), 
+                    sprite: Sprite {
+                        color: robot.color,
+                        ..default()
+                    },
+                    ..default()
+                },
+                Player{ id },
+                Movement::default(),
+            ),
+        );
+    }
 
     commands.insert_resource(TickTimer {
-            timer: Timer::new(Duration::from_secs(1), TimerMode::Repeating),
+            timer: Timer::new(Duration::from_millis(simulation_config.tick_ms), TimerMode::Repeating),
         }
     );
 }
 
 
+// Slides every sprite from its previous tile to the current one, in a bit less than a tick,
+// so it's always on the tile by the time the robot moves again
 pub fn move_player(
     world_res: Res<WorldRes>,
     // Coordinate globali
-    mut player_query: Query<&mut Transform, With<Player>>,
+    mut player_query: Query<(&mut Transform, &mut Movement, &Player)>,
+    timer_res: Res<TickTimer>,
     time: Res<Time>,
 ) {
-    let mut player = &mut player_query.get_single_mut().unwrap();
-    
-    // Old coordinates
-    let old_x = player.translation.x;
-    let old_y = player.translation.y;
-    // New Coordinates
-    let new_x = world_res.player_x;
-    let new_y = world_res.player_y;
-    // println!("NEw x: {}, New y: {}, oldx; {}, old_y: {}", new_x, new_y, old_x, old_y);
-
-    if old_x != new_y as f32 * FACTOR + TRANSLATE_X || -1.0 * old_y as f32 != new_x as f32 * FACTOR + TRANSLATE_Y {
-    // Calculate the translation
-        // TODO Accound for matrix world position 
-    // Disabled tranition
-    // Coordinates are swapped because of different coordinate system
-        player.translation.x = new_y as f32 * FACTOR + TRANSLATE_X * world_res.world_size as f32;
-        player.translation.y = new_x as f32 * FACTOR + TRANSLATE_Y * world_res.world_size as f32;
-        player.translation.y *= -1.0;
+    let duration = timer_res.timer.duration().as_secs_f32() * MOVE_TICK_FRACTION;
+    for (mut player, mut movement, player_id) in player_query.iter_mut() {
+        let robot = &world_res.robots[player_id.id];
+        let target = robot_to_world((robot.player_x, robot.player_y), world_res.world_size);
+
+        if target != movement.to {
+            let current = player.translation.truncate();
+            // Teleports and the first position are not animated
+            let far = current.distance(target) > TILE_SIZE * 1.5;
+            movement.from = if far { target } else { current };
+            movement.to = target;
+            movement.progress = 0.0;
+        }
+
+        if movement.progress < 1.0 {
+            movement.progress = if duration > 0.0 {
+                (movement.progress + time.delta_seconds() / duration).min(1.0)
+            } else {
+                1.0
+            };
+        }
+        let position = movement.from.lerp(movement.to, movement.progress);
+        player.translation.x = position.x;
+        player.translation.y = position.y;
     }
 }
 
 pub fn change_tick_speed(
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    simulation_config: Res<SimulationConfig>,
     mut timer_res: ResMut<TickTimer> 
 ) {
 
-    if keyboard_input.pressed(KeyCode::Z) {
-        timer_res.timer.set_duration(Duration::from_millis(100));
+    if key_bindings.fast_speed.pressed(&keyboard_input) {
+        timer_res.timer.set_duration(Duration::from_millis(simulation_config.fast_tick_ms));
     }
 
 
-    if keyboard_input.pressed(KeyCode::X) {
-        timer_res.timer.set_duration(Duration::from_secs(1));
+    if key_bindings.normal_speed.pressed(&keyboard_input) {
+        timer_res.timer.set_duration(Duration::from_millis(simulation_config.tick_ms));
     }
 
 }
 
 pub fn pause_tick(
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
     mut timer_res: ResMut<TickTimer> 
 ) {
-    if keyboard_input.pressed(KeyCode::P) {
+    if key_bindings.pause.pressed(&keyboard_input) {
         timer_res.timer.pause();
     }
 }
 
 pub fn unpause_tick(
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
     mut timer_res: ResMut<TickTimer> 
 ) {
-    if keyboard_input.pressed(KeyCode::O) {
+    if key_bindings.unpause.pressed(&keyboard_input) {
         if timer_res.timer.paused() {
             timer_res.timer.unpause();
         }
@@ -125,21 +176,71 @@ pub fn unpause_tick(
 }
 
 pub fn tick_loop (
-    mut world_res: ResMut<WorldRes>,
-    mut runner_res: NonSendMut<Runner>,
+    world_res: Res<WorldRes>,
+    mut runners: NonSendMut<Runners>,
     mut timer: ResMut<TickTimer>,
+    simulation_config: Res<SimulationConfig>,
     time: Res<Time>,
 ) {
     
     timer.timer.tick(time.delta());
 
     if timer.timer.finished() {
-        let _ = runner_res.game_tick();
-        
-        let robot = runner_res.get_robot();
-        // Update player coordinates
-        let coordinates = robot.get_coordinate();
-        world_res.player_x = coordinates.get_row();
-        world_res.player_y = coordinates.get_col();
+        // The new positions arrive with the other messages, in update_map
+        for (runner_res, robot_state) in runners.0.iter_mut().zip(world_res.robots.iter()) {
+            // The screen is too far behind this robot, it waits for update_map to catch up
+            if robot_state.queue_depth.get() >= simulation_config.max_queued_messages {
+                continue;
+            }
+            let _ = runner_res.game_tick();
+        }
+    }
+}
+
+// Turns the robotics_lib events sent by the robots into bevy events
+pub fn forward_robot_events(
+    world_res: Res<WorldRes>,
+    mut robot_events: EventWriter<RobotEvent>,
+) {
+    for (robot, robot_state) in world_res.robots.iter().enumerate() {
+        let events_rx = robot_state.events_rx.lock().unwrap();
+        // Every tick sends a few events, so all of them are read every frame
+        while let Ok((tick, kind)) = events_rx.try_recv() {
+            robot_events.send(RobotEvent { robot, tick, kind });
+        }
+    }
+}
+
+// Colour of the planned route and target, by what the robot is going for
+fn plan_color(kind: PlanKind) -> Color {
+    match kind {
+        PlanKind::GetContent => Color::ORANGE,
+        PlanKind::PutContent => Color::LIME_GREEN,
+        PlanKind::Explore => Color::CYAN,
+    }
+}
+
+// Draws the estimated route of every robot, dashed since op_map may take another one,
+// and a box around its target.
+// The plan is only sent when it changes, so a replan shows up on the next frame
+pub fn draw_robot_plans(world_res: Res<WorldRes>, mut gizmos: Gizmos) {
+    let size = world_res.world_size;
+    for robot in world_res.robots.iter() {
+        let Some(plan) = &robot.plan else {
+            continue;
+        };
+        let color = plan_color(plan.kind);
+        for step in plan.path.windows(2) {
+            let from = robot_to_world(step[0], size);
+            let to = robot_to_world(step[1], size);
+            // One dash per step
+            gizmos.line_2d(from, from.lerp(to, 0.6), color);
+        }
+        // Last step, from the end of the path to the target
+        if let Some(last) = plan.path.last() {
+            gizmos.line_2d(robot_to_world(*last, size), robot_to_world(plan.target, size), color.with_a(0.5));
+        }
+        let target = robot_to_world(plan.target, size);
+        gizmos.rect_2d(target, 0.0, Vec2::splat(TILE_SIZE - 2.0), color);
     }
 }
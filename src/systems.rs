@@ -1,16 +1,18 @@
 use bevy::prelude::*;
 use crate::AppState;
+use crate::config::KeyBindings;
 use bevy::app::AppExit;
 use bevy_kira_audio::Audio;
 use bevy_kira_audio::AudioControl;
 
 pub fn exit_game(
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
     // We use EventWriter<T> to send an event
     // and EventReader<T> to receive events
     mut app_exit_event_writer: EventWriter<AppExit>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if key_bindings.exit.just_pressed(&keyboard_input) {
         app_exit_event_writer.send(AppExit);
     }
 }
@@ -18,10 +20,11 @@ pub fn exit_game(
 pub fn transition_to_game_state(
     mut commands: Commands,
     keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
     app_state: Res<State<AppState>>,
     mut next_app_state: ResMut<NextState<AppState>>
 ) {
-    if keyboard_input.just_pressed(KeyCode::G) {
+    if key_bindings.start_game.just_pressed(&keyboard_input) {
         if *app_state.get() != AppState::Game {
             next_app_state.set(AppState::Game);
             println!("Entered Appstate::Game");
@@ -0,0 +1,122 @@
+use robotics_lib::runner::Runnable;
+use robotics_lib::utils::LibError;
+use robotics_lib::world::World;
+use rust_eze_spotlight::Spotlight;
+use serde::Deserialize;
+
+// A robotics_lib tool a robot can use, as written in the config file
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum ToolConfig {
+    // Reveals every tile within `radius` of the robot
+    Spotlight {
+        radius: usize,
+        // The policy only uses it above this energy
+        #[serde(default = "default_min_energy")]
+        min_energy: usize,
+        // Ticks to wait before the policy uses it again
+        #[serde(default = "default_cooldown")]
+        cooldown: usize,
+    },
+}
+
+fn default_min_energy() -> usize {
+    300
+}
+
+fn default_cooldown() -> usize {
+    20
+}
+
+impl ToolConfig {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToolConfig::Spotlight { .. } => "Spotlight",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ToolConfig::Spotlight { radius, .. } => {
+                if *radius == 0 {
+                    return Err("the radius of Spotlight must be greater than 0".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // How far from the robot the tiles the tool reveals can be
+    pub fn reach(&self) -> usize {
+        match self {
+            ToolConfig::Spotlight { radius, .. } => *radius,
+        }
+    }
+
+    fn min_energy(&self) -> usize {
+        match self {
+            ToolConfig::Spotlight { min_energy, .. } => *min_energy,
+        }
+    }
+
+    fn cooldown(&self) -> usize {
+        match self {
+            ToolConfig::Spotlight { cooldown, .. } => *cooldown,
+        }
+    }
+
+    // Runs the tool, returns the energy it used
+    pub fn invoke(&self, robot: &mut impl Runnable, world: &mut World) -> Result<usize, LibError> {
+        let energy_before = robot.get_energy().get_energy_level();
+        match self {
+            ToolConfig::Spotlight { radius, .. } => {
+                Spotlight::illuminate(robot, world, *radius)?;
+            }
+        }
+        Ok(energy_before.saturating_sub(robot.get_energy().get_energy_level()))
+    }
+}
+
+struct RegisteredTool {
+    config: ToolConfig,
+    last_used: Option<usize>,
+}
+
+// The tools of one robot, and when they were last used
+pub struct ToolRegistry {
+    tools: Vec<RegisteredTool>,
+}
+
+impl ToolRegistry {
+    pub fn new(configs: &[ToolConfig]) -> Self {
+        ToolRegistry {
+            tools: configs
+                .iter()
+                .map(|config| RegisteredTool { config: config.clone(), last_used: None })
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ToolConfig> {
+        self.tools.get(index).map(|tool| &tool.config)
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.tools.iter().position(|tool| tool.config.name() == name)
+    }
+
+    // The first tool the policy is allowed to use right now
+    pub fn ready(&self, tick: usize, energy: usize) -> Option<usize> {
+        self.tools.iter().position(|tool| {
+            let cooled_down = tool
+                .last_used
+                .map_or(true, |last_used| tick >= last_used + tool.config.cooldown());
+            cooled_down && energy >= tool.config.min_energy()
+        })
+    }
+
+    pub fn mark_used(&mut self, index: usize, tick: usize) {
+        if let Some(tool) = self.tools.get_mut(index) {
+            tool.last_used = Some(tick);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_ecs_tilemap::prelude::*;
+use bevy_ecs_tilemap::tiles::AnimatedTile;
+use bevy_ecs_tilemap::TilemapBundle;
+use robotics_lib::world::tile::Tile;
+
+use crate::camera::components::CameraMaker;
+use crate::player::messages::Coords;
+use crate::world::components::{ContentDraw, GridDraw, TileDraw};
+use crate::world::coords::{chunk_bounds, chunk_of, chunk_origin, chunk_tile_pos, chunks_per_side, world_to_robot, CHUNK_SIZE, TILE_SIZE};
+use crate::world::resources::WorldRes;
+use crate::world::systems::{content_badge, spawn_content_badge};
+use crate::world::themes::{Atlas, Themes};
+
+// Chunks spawned in a single frame, zooming out on a big world would freeze it otherwise
+const MAX_CHUNKS_PER_FRAME: usize = 8;
+// Chunks around the screen kept spawned, so moving the camera a bit doesn't respawn them
+const CHUNK_MARGIN: usize = 1;
+
+// The two tilemaps of a spawned chunk and their tiles, row by row from its first tile,
+// and the content badges of its tiles
+pub struct LoadedChunk {
+    pub grid: Entity,
+    pub content: Entity,
+    pub grid_tiles: Vec<Entity>,
+    pub content_tiles: Vec<Entity>,
+    pub badges: HashMap<Coords, Entity>,
+}
+
+// The map is split in CHUNK_SIZE x CHUNK_SIZE chunks, only the ones with a known tile that are
+// on screen are spawned
+#[derive(Resource)]
+pub struct Chunks {
+    pub map_size: usize,
+    // Whether any robot saw a tile of the chunk, by chunk row and col
+    pub known: Vec<Vec<bool>>,
+    pub loaded: HashMap<Coords, LoadedChunk>,
+}
+
+impl Chunks {
+    pub fn new(map_size: usize) -> Self {
+        let side = chunks_per_side(map_size);
+        Chunks {
+            map_size,
+            known: vec![vec![false; side]; side],
+            loaded: HashMap::new(),
+        }
+    }
+
+    // A robot saw this tile, its chunk can be spawned
+    pub fn reveal(&mut self, coords: Coords) {
+        if coords.0 >= self.map_size || coords.1 >= self.map_size {
+            return;
+        }
+        let (chunk_x, chunk_y) = chunk_of(coords);
+        if !self.known[chunk_x][chunk_y] {
+            self.known[chunk_x][chunk_y] = true;
+        }
+    }
+
+    // The grid and content tile entities of a tile, None if its chunk is not spawned
+    pub fn tile_entities(&self, (x, y): Coords) -> Option<(Entity, Entity)> {
+        if x >= self.map_size || y >= self.map_size {
+            return None;
+        }
+        let chunk = chunk_of((x, y));
+        let loaded = self.loaded.get(&chunk)?;
+        let (first, _, cols) = chunk_bounds(chunk, self.map_size);
+        let index = (x - first.0) * cols + (y - first.1);
+        Some((loaded.grid_tiles[index], loaded.content_tiles[index]))
+    }
+
+    // Every tile of the spawned chunks, in robot coordinates
+    pub fn loaded_tiles(&self) -> impl Iterator<Item = Coords> + '_ {
+        self.loaded.keys().flat_map(move |chunk| {
+            let (first, rows, cols) = chunk_bounds(*chunk, self.map_size);
+            (0..rows).flat_map(move |i| (0..cols).map(move |j| (first.0 + i, first.1 + j)))
+        })
+    }
+}
+
+// Spawns the chunks on screen that have something to show and despawns the ones that left it
+pub fn update_chunks(
+    mut commands: Commands,
+    mut chunks: ResMut<Chunks>,
+    world: Res<WorldRes>,
+    themes: Res<Themes>,
+    asset_server: Res<AssetServer>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<CameraMaker>>,
+) {
+    let (Ok(window), Ok((camera, projection))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let size = chunks.map_size;
+    if size == 0 {
+        return;
+    }
+
+    // The part of the map on screen, clamped so the corners are always on a tile
+    let half_view = Vec2::new(window.width(), window.height()) * projection.scale / 2.0;
+    let half_map = size as f32 * TILE_SIZE / 2.0 - 0.01;
+    let clamp = |position: Vec2| position.clamp(Vec2::splat(-half_map), Vec2::splat(half_map));
+    let center = camera.translation.truncate();
+    let top_left = world_to_robot(clamp(center + Vec2::new(-half_view.x, half_view.y)), size).unwrap_or((0, 0));
+    let bottom_right = world_to_robot(clamp(center + Vec2::new(half_view.x, -half_view.y)), size).unwrap_or((size - 1, size - 1));
+    let last = chunks_per_side(size) - 1;
+    let rows = (top_left.0 / CHUNK_SIZE).saturating_sub(CHUNK_MARGIN)..=(bottom_right.0 / CHUNK_SIZE + CHUNK_MARGIN).min(last);
+    let cols = (top_left.1 / CHUNK_SIZE).saturating_sub(CHUNK_MARGIN)..=(bottom_right.1 / CHUNK_SIZE + CHUNK_MARGIN).min(last);
+    let on_screen = |(chunk_x, chunk_y): &Coords| rows.contains(chunk_x) && cols.contains(chunk_y);
+
+    let off_screen: Vec<Coords> = chunks.loaded.keys().filter(|chunk| !on_screen(chunk)).copied().collect();
+    for chunk in off_screen {
+        if let Some(loaded) = chunks.loaded.remove(&chunk) {
+            despawn_chunk(&mut commands, loaded);
+        }
+    }
+
+    let mut spawned = 0;
+    for chunk_x in rows.clone() {
+        for chunk_y in cols.clone() {
+            if spawned == MAX_CHUNKS_PER_FRAME {
+                return;
+            }
+            if !chunks.known[chunk_x][chunk_y] || chunks.loaded.contains_key(&(chunk_x, chunk_y)) {
+                continue;
+            }
+            let loaded = spawn_chunk(&mut commands, &asset_server, themes.current(), &world, (chunk_x, chunk_y));
+            chunks.loaded.insert((chunk_x, chunk_y), loaded);
+            spawned += 1;
+        }
+    }
+}
+
+// Both layers of a chunk, drawn with what the robots know right now
+fn spawn_chunk(commands: &mut Commands, asset_server: &AssetServer, atlas: &Atlas, world: &WorldRes, chunk: Coords) -> LoadedChunk {
+    let map_size = world.world_size;
+    let (first, rows, cols) = chunk_bounds(chunk, map_size);
+    let size = TilemapSize { x: cols as u32, y: rows as u32 };
+    let tile_size = TilemapTileSize { x: TILE_SIZE, y: TILE_SIZE };
+    let grid_size = tile_size.into();
+    let map_type = TilemapType::default();
+    let origin = chunk_origin(chunk, map_size);
+
+    let mut layer = |z: f32, image: &str, texture: &dyn Fn(Option<&Tile>) -> (u32, Option<AnimatedTile>)| {
+        let tilemap_entity = commands.spawn_empty().id();
+        let mut storage = TileStorage::empty(size);
+        let mut tiles = Vec::with_capacity(rows * cols);
+        for x in first.0..first.0 + rows {
+            for y in first.1..first.1 + cols {
+                let position = chunk_tile_pos((x, y), map_size).expect("The chunk is in the map");
+                let tile = world.world.as_ref().and_then(|known| known[x][y].as_ref());
+                let (index, animation) = texture(tile);
+                let mut tile_entity = commands.spawn((
+                    TileBundle {
+                        position,
+                        tilemap_id: TilemapId(tilemap_entity),
+                        texture_index: TileTextureIndex(index),
+                        ..Default::default()
+                    },
+                    TileDraw{},
+                ));
+                if let Some(animation) = animation {
+                    tile_entity.insert(animation);
+                }
+                let tile_entity = tile_entity.id();
+                storage.set(&position, tile_entity);
+                tiles.push(tile_entity);
+            }
+        }
+        commands.entity(tilemap_entity).insert(TilemapBundle {
+            grid_size,
+            map_type,
+            size,
+            storage,
+            texture: TilemapTexture::Single(asset_server.load(image)),
+            tile_size,
+            transform: Transform::from_translation(origin.extend(z)),
+            ..Default::default()
+        });
+        (tilemap_entity, tiles)
+    };
+
+    let (grid, grid_tiles) = layer(0.0, &atlas.tiles, &|tile| match tile {
+        Some(tile) => (atlas.tile_index(tile), atlas.tile_animation(tile)),
+        None => (atlas.unknown_tile, None),
+    });
+    let (content, content_tiles) = layer(1.0, &atlas.contents, &|tile| match tile {
+        Some(tile) => (atlas.content_index(tile), atlas.content_animation(tile)),
+        None => (atlas.no_content, None),
+    });
+    commands.entity(grid).insert(GridDraw{});
+    commands.entity(content).insert(ContentDraw{});
+
+    let mut badges = HashMap::new();
+    if let Some(known) = world.world.as_ref() {
+        for x in first.0..first.0 + rows {
+            for y in first.1..first.1 + cols {
+                let label = known[x][y].as_ref().and_then(|tile| content_badge(&tile.content));
+                if let Some(label) = label {
+                    badges.insert((x, y), spawn_content_badge(commands, asset_server, label, (x, y), map_size));
+                }
+            }
+        }
+    }
+
+    LoadedChunk { grid, content, grid_tiles, content_tiles, badges }
+}
+
+fn despawn_chunk(commands: &mut Commands, loaded: LoadedChunk) {
+    for entity in loaded.grid_tiles.into_iter().chain(loaded.content_tiles).chain(loaded.badges.into_values()) {
+        commands.entity(entity).despawn();
+    }
+    commands.entity(loaded.grid).despawn();
+    commands.entity(loaded.content).despawn();
+}
+
+// The chunks of the last game, the next one starts with none
+pub fn despawn_chunks(mut commands: Commands, mut chunks: ResMut<Chunks>) {
+    for (_, loaded) in chunks.loaded.drain() {
+        despawn_chunk(&mut commands, loaded);
+    }
+}
@@ -11,3 +11,21 @@ pub struct GridDraw{}
 
 #[derive(Component)]
 pub struct ContentDraw{}
+
+// Darkens the screen at night
+#[derive(Component)]
+pub struct NightOverlay{}
+
+// Covers the screen when it's foggy, except around the followed robot
+#[derive(Component)]
+pub struct FogOverlay{}
+
+// A drop of rain or a snowflake, the velocity is in screen pixels per second
+#[derive(Component)]
+pub struct WeatherParticle {
+    pub velocity: Vec2,
+}
+
+// The quantity of the content of a tile, drawn on its bottom right corner
+#[derive(Component)]
+pub struct ContentBadge{}
@@ -0,0 +1,220 @@
+use bevy::prelude::Vec2;
+use bevy_ecs_tilemap::prelude::TilePos;
+use crate::player::messages::Coords;
+
+// Remember that the robot coordinates as swapped
+// ROBOT
+// ------------------> y
+// |
+// |
+// V
+// x
+//
+// WORLD
+// y
+// ^
+// |
+// |
+// -------------------> x
+
+// The tile drawn for robot coordinates (x, y), None outside of the map
+pub fn robot_to_tile_pos((x, y): Coords, map_size: usize) -> Option<TilePos> {
+    if x >= map_size || y >= map_size {
+        return None;
+    }
+    Some(TilePos {
+        x: y as u32,
+        y: (map_size - 1 - x) as u32,
+    })
+}
+
+// The opposite of robot_to_tile_pos
+pub fn tile_pos_to_robot(position: &TilePos, map_size: usize) -> Option<Coords> {
+    let (x, y) = (position.x as usize, position.y as usize);
+    if x >= map_size || y >= map_size {
+        return None;
+    }
+    Some((map_size - 1 - y, x))
+}
+
+// Side of a tile in world units, the same for both tilemaps
+pub const TILE_SIZE: f32 = 16.0;
+
+// Center of the tile in world coordinates. The tilemaps are centered on the origin
+// by get_tilemap_center_transform, so the middle of the map is at (0, 0)
+pub fn robot_to_world((x, y): Coords, map_size: usize) -> Vec2 {
+    let offset = (map_size as f32 - 1.0) * TILE_SIZE / 2.0;
+    Vec2::new(
+        y as f32 * TILE_SIZE - offset,
+        (map_size as f32 - 1.0 - x as f32) * TILE_SIZE - offset,
+    )
+}
+
+// The opposite of robot_to_world, None outside of the map
+pub fn world_to_robot(position: Vec2, map_size: usize) -> Option<Coords> {
+    let half = map_size as f32 * TILE_SIZE / 2.0;
+    let col = ((position.x + half) / TILE_SIZE).floor();
+    let row_from_bottom = ((position.y + half) / TILE_SIZE).floor();
+    if col < 0.0 || row_from_bottom < 0.0 || col >= map_size as f32 || row_from_bottom >= map_size as f32 {
+        return None;
+    }
+    Some((map_size - 1 - row_from_bottom as usize, col as usize))
+}
+
+// Side of a chunk in tiles, every chunk is a pair of small tilemaps
+pub const CHUNK_SIZE: usize = 32;
+
+pub fn chunks_per_side(map_size: usize) -> usize {
+    (map_size + CHUNK_SIZE - 1) / CHUNK_SIZE
+}
+
+// The chunk a tile is in, as (chunk row, chunk col)
+pub fn chunk_of((x, y): Coords) -> Coords {
+    (x / CHUNK_SIZE, y / CHUNK_SIZE)
+}
+
+// First tile of a chunk and how many rows and cols it has, the last ones can be smaller
+pub fn chunk_bounds((chunk_x, chunk_y): Coords, map_size: usize) -> (Coords, usize, usize) {
+    let first = (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE);
+    let rows = CHUNK_SIZE.min(map_size - first.0);
+    let cols = CHUNK_SIZE.min(map_size - first.1);
+    (first, rows, cols)
+}
+
+// The tile drawn for robot coordinates (x, y) in the tilemaps of its chunk, flipped like robot_to_tile_pos
+pub fn chunk_tile_pos((x, y): Coords, map_size: usize) -> Option<TilePos> {
+    if x >= map_size || y >= map_size {
+        return None;
+    }
+    let (first, rows, _) = chunk_bounds(chunk_of((x, y)), map_size);
+    Some(TilePos {
+        x: (y - first.1) as u32,
+        y: (first.0 + rows - 1 - x) as u32,
+    })
+}
+
+// Where the tilemaps of a chunk go: their tile (0, 0), the bottom left one, is on this point
+pub fn chunk_origin(chunk: Coords, map_size: usize) -> Vec2 {
+    let (first, rows, _) = chunk_bounds(chunk, map_size);
+    robot_to_world((first.0 + rows - 1, first.1), map_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs_tilemap::prelude::{get_tilemap_center_transform, TilemapGridSize, TilemapSize, TilemapType};
+
+    #[test]
+    fn corners_are_flipped() {
+        // Row 0 is the top row for both, but the tilemap counts y from the bottom
+        assert_eq!(robot_to_tile_pos((0, 0), 30), Some(TilePos { x: 0, y: 29 }));
+        assert_eq!(robot_to_tile_pos((0, 29), 30), Some(TilePos { x: 29, y: 29 }));
+        assert_eq!(robot_to_tile_pos((29, 0), 30), Some(TilePos { x: 0, y: 0 }));
+        assert_eq!(robot_to_tile_pos((29, 29), 30), Some(TilePos { x: 29, y: 0 }));
+    }
+
+    #[test]
+    fn outside_of_the_map_is_none() {
+        assert_eq!(robot_to_tile_pos((30, 0), 30), None);
+        assert_eq!(robot_to_tile_pos((0, 30), 30), None);
+        assert_eq!(tile_pos_to_robot(&TilePos { x: 30, y: 0 }, 30), None);
+        assert_eq!(tile_pos_to_robot(&TilePos { x: 0, y: 30 }, 30), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        for x in 0..5 {
+            for y in 0..5 {
+                let position = robot_to_tile_pos((x, y), 5).unwrap();
+                assert_eq!(tile_pos_to_robot(&position, 5), Some((x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn world_center_of_the_corners() {
+        // 30 tiles of 16 units, centered on the origin: the corners are at +-232
+        assert_eq!(robot_to_world((0, 0), 30), Vec2::new(-232.0, 232.0));
+        assert_eq!(robot_to_world((0, 29), 30), Vec2::new(232.0, 232.0));
+        assert_eq!(robot_to_world((29, 0), 30), Vec2::new(-232.0, -232.0));
+        assert_eq!(robot_to_world((29, 29), 30), Vec2::new(232.0, -232.0));
+    }
+
+    #[test]
+    fn world_matches_the_tilemap_transform() {
+        // Same transform as spawn_map, for any size the robot is drawn on its tile
+        let grid_size = TilemapGridSize { x: TILE_SIZE, y: TILE_SIZE };
+        let map_type = TilemapType::default();
+        for size in [1, 2, 30, 51, 300, 1000] {
+            let map_size = TilemapSize { x: size as u32, y: size as u32 };
+            let transform = get_tilemap_center_transform(&map_size, &grid_size, &map_type, 0.0);
+            for coords in [(0, 0), (0, size - 1), (size - 1, 0), (size - 1, size - 1), (size / 2, size / 3)] {
+                let tile_pos = robot_to_tile_pos(coords, size).unwrap();
+                let expected = transform.translation.truncate() + tile_pos.center_in_world(&grid_size, &map_type);
+                assert_eq!(robot_to_world(coords, size), expected, "size {} coords {:?}", size, coords);
+            }
+        }
+    }
+
+    #[test]
+    fn neighbours_are_one_tile_apart() {
+        let center = robot_to_world((10, 10), 50);
+        assert_eq!(robot_to_world((9, 10), 50) - center, Vec2::new(0.0, TILE_SIZE));
+        assert_eq!(robot_to_world((11, 10), 50) - center, Vec2::new(0.0, -TILE_SIZE));
+        assert_eq!(robot_to_world((10, 11), 50) - center, Vec2::new(TILE_SIZE, 0.0));
+    }
+
+    #[test]
+    fn world_round_trip() {
+        for size in [1, 5, 30] {
+            for x in 0..size {
+                for y in 0..size {
+                    let center = robot_to_world((x, y), size);
+                    assert_eq!(world_to_robot(center, size), Some((x, y)));
+                    // Anywhere in the tile, not only the center
+                    assert_eq!(world_to_robot(center + Vec2::new(7.9, -7.9), size), Some((x, y)));
+                }
+            }
+        }
+        assert_eq!(world_to_robot(Vec2::new(-240.1, 0.0), 30), None);
+        assert_eq!(world_to_robot(Vec2::new(0.0, 240.0), 30), None);
+    }
+
+    #[test]
+    fn last_chunks_are_smaller() {
+        assert_eq!(chunks_per_side(64), 2);
+        assert_eq!(chunks_per_side(70), 3);
+        assert_eq!(chunk_bounds((0, 0), 70), ((0, 0), 32, 32));
+        assert_eq!(chunk_bounds((2, 1), 70), ((64, 32), 6, 32));
+        assert_eq!(chunk_bounds((2, 2), 70), ((64, 64), 6, 6));
+    }
+
+    #[test]
+    fn chunk_tiles_are_flipped() {
+        // Row 0 is the top row of its chunk too
+        assert_eq!(chunk_tile_pos((0, 0), 70), Some(TilePos { x: 0, y: 31 }));
+        assert_eq!(chunk_tile_pos((31, 31), 70), Some(TilePos { x: 31, y: 0 }));
+        assert_eq!(chunk_tile_pos((64, 65), 70), Some(TilePos { x: 1, y: 5 }));
+        assert_eq!(chunk_tile_pos((69, 69), 70), Some(TilePos { x: 5, y: 0 }));
+        assert_eq!(chunk_tile_pos((70, 0), 70), None);
+    }
+
+    #[test]
+    fn chunk_tiles_are_where_the_robot_is_drawn() {
+        // Same tile size as the chunk tilemaps, whose tile (0, 0) is on chunk_origin
+        for size in [10, 70, 1000] {
+            for coords in [(0, 0), (size - 1, size - 1), (size / 2, size / 3), (size - 1, 0)] {
+                let position = chunk_tile_pos(coords, size).unwrap();
+                let center = chunk_origin(chunk_of(coords), size) + Vec2::new(position.x as f32, position.y as f32) * TILE_SIZE;
+                assert_eq!(center, robot_to_world(coords, size), "size {} coords {:?}", size, coords);
+            }
+        }
+    }
+
+    #[test]
+    fn one_tile_map() {
+        assert_eq!(robot_to_tile_pos((0, 0), 1), Some(TilePos { x: 0, y: 0 }));
+        assert_eq!(tile_pos_to_robot(&TilePos { x: 0, y: 0 }, 1), Some((0, 0)));
+        assert_eq!(robot_to_world((0, 0), 1), Vec2::ZERO);
+    }
+}
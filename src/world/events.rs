@@ -0,0 +1,6 @@
+use bevy::prelude::*;
+use crate::player::messages::Coords;
+
+// Tiles whose content or type changed this frame, sent by update_map after the map is patched
+#[derive(Event)]
+pub struct TilesUpdated(pub Vec<Coords>);
@@ -1,4 +1,5 @@
-// use rand::Rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use robotics_lib::energy::Energy;
 use robotics_lib::event::events::Event;
 use robotics_lib::interface::{robot_map, Tools, where_am_i};
@@ -8,7 +9,7 @@ use robotics_lib::runner::backpack::BackPack;
 use robotics_lib::runner::{Robot, Runnable, Runner};
 use robotics_lib::world::coordinates::Coordinate;
 use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
-use robotics_lib::world::environmental_conditions::WeatherType::{Rainy, Sunny};
+use robotics_lib::world::environmental_conditions::WeatherType::{Foggy, Rainy, Sunny};
 use robotics_lib::world::tile::Content::{Bank, Bin, Coin, Crate, Fire, Fish, Garbage, Market, Rock, Tree};
 use robotics_lib::world::tile::TileType::{
     DeepWater, Grass, Hill, Lava, Mountain, Sand, ShallowWater, Snow, Street, Teleport,
@@ -26,14 +27,54 @@ use oxagworldgenerator::world_generator::presets::content_presets::OxAgContentPr
 use oxagworldgenerator::world_generator::tile_type_options::OxAgTileTypeOptions;
 use worldgen_unwrap::public::WorldgeneratorUnwrap;
 use crate::AI::training::generate_generator;
+use crate::world::resources::{GeneratorKind, WorldSettings};
+use std::path::PathBuf;
 
-pub struct WorldGenerator {
-    size: usize,
+// Everything a Generator gives to the Runner
+type GeneratedWorld = (
+    Vec<Vec<Tile>>,
+    (usize, usize),
+    EnvironmentalConditions,
+    f32,
+    Option<HashMap<Content, f32>>,
+);
+
+// The generator used by the Runner, chosen from the WorldSettings
+pub enum WorldGenerator {
+    OxAg(OxAgWorldGenerator),
+    // The file is loaded up front, so that the size of the world is known before the Runner is built
+    File(GeneratedWorld),
+    Procedural(ProceduralGenerator),
 }
 
 impl WorldGenerator {
-    pub fn init(size: usize) -> Self {
-        WorldGenerator { size }
+    pub fn from_settings(settings: &WorldSettings, seed: u64) -> Result<Self, String> {
+        settings.validate()?;
+
+        let generator = match settings.generator {
+            GeneratorKind::OxAg => {
+                WorldGenerator::OxAg(generate_generator(seed, settings.world_size, settings.content_preset))
+            }
+            GeneratorKind::File => {
+                let path: PathBuf = settings.world_file.clone().expect("Checked by validate");
+                if !path.is_file() {
+                    return Err(format!("world file '{}' does not exist", path.display()));
+                }
+                WorldGenerator::File(WorldgeneratorUnwrap::init(false, Some(path)).gen())
+            }
+            GeneratorKind::Procedural => {
+                WorldGenerator::Procedural(ProceduralGenerator::init(settings.world_size, seed))
+            }
+        };
+        Ok(generator)
+    }
+
+    // The size of the world that will be generated, a world file decides its own size
+    pub fn world_size(&self, settings: &WorldSettings) -> usize {
+        match self {
+            WorldGenerator::File(world) => world.0.len(),
+            _ => settings.world_size,
+        }
     }
 }
 
@@ -47,11 +88,123 @@ impl Generator for WorldGenerator {
         f32,
         Option<HashMap<Content, f32>>,
     ) {
-        const SEED: u64 = 421;
+        match self {
+            WorldGenerator::OxAg(generator) => generator.gen(),
+            WorldGenerator::File(world) => world.clone(),
+            WorldGenerator::Procedural(generator) => generator.gen(),
+        }
+    }
+}
 
-        let temp = generate_generator(SEED).gen();
+// A small noise based generator, the same seed always gives the same world
+pub struct ProceduralGenerator {
+    size: usize,
+    seed: u64,
+}
 
-        return temp;
+impl ProceduralGenerator {
+    // Distance in tiles between two points of the noise grid
+    const NOISE_STEP: usize = 8;
+
+    pub fn init(size: usize, seed: u64) -> Self {
+        ProceduralGenerator { size, seed }
+    }
+
+    // Value noise: random values on a coarse grid, interpolated in between.
+    // Returns a height between 0 and 1 for every tile
+    fn heights(&self, rng: &mut StdRng) -> Vec<Vec<f32>> {
+        let grid_size = self.size / Self::NOISE_STEP + 2;
+        let grid: Vec<Vec<f32>> = (0..grid_size)
+            .map(|_| (0..grid_size).map(|_| rng.gen_range(0.0..1.0)).collect())
+            .collect();
+
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let step = Self::NOISE_STEP as f32;
+
+        (0..self.size)
+            .map(|row| {
+                (0..self.size)
+                    .map(|col| {
+                        let (gx, gy) = (row / Self::NOISE_STEP, col / Self::NOISE_STEP);
+                        let tx = smooth((row % Self::NOISE_STEP) as f32 / step);
+                        let ty = smooth((col % Self::NOISE_STEP) as f32 / step);
+                        let top = grid[gx][gy] * (1.0 - ty) + grid[gx][gy + 1] * ty;
+                        let bottom = grid[gx + 1][gy] * (1.0 - ty) + grid[gx + 1][gy + 1] * ty;
+                        top * (1.0 - tx) + bottom * tx
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn tile_type(height: f32) -> TileType {
+        match height {
+            h if h < 0.25 => DeepWater,
+            h if h < 0.35 => ShallowWater,
+            h if h < 0.40 => Sand,
+            h if h < 0.65 => Grass,
+            h if h < 0.75 => Hill,
+            h if h < 0.85 => Mountain,
+            _ => Snow,
+        }
+    }
+
+    fn content(tile_type: &TileType, rng: &mut StdRng) -> Content {
+        let roll: f32 = rng.gen_range(0.0..1.0);
+        match tile_type {
+            Grass if roll < 0.03 => Garbage(rng.gen_range(1..=3)),
+            Grass if roll < 0.035 => Bin(0..10),
+            Grass if roll < 0.12 => Tree(rng.gen_range(1..=3)),
+            Sand if roll < 0.02 => Garbage(rng.gen_range(1..=2)),
+            Sand if roll < 0.03 => Coin(rng.gen_range(1..=5)),
+            Hill if roll < 0.08 => Rock(rng.gen_range(1..=3)),
+            Mountain if roll < 0.1 => Rock(rng.gen_range(1..=5)),
+            ShallowWater if roll < 0.03 => Fish(rng.gen_range(1..=3)),
+            _ => Content::None,
+        }
     }
 }
 
+impl Generator for ProceduralGenerator {
+    fn gen(
+        &mut self,
+    ) -> (
+        Vec<Vec<Tile>>,
+        (usize, usize),
+        EnvironmentalConditions,
+        f32,
+        Option<HashMap<Content, f32>>,
+    ) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let heights = self.heights(&mut rng);
+
+        let map: Vec<Vec<Tile>> = heights
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|height| {
+                        let tile_type = Self::tile_type(*height);
+                        let content = Self::content(&tile_type, &mut rng);
+                        Tile {
+                            tile_type,
+                            content,
+                            elevation: (height * 100.0) as usize,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Spawn on the walkable tile nearest to the center
+        let center = self.size / 2;
+        let spawn = (0..self.size)
+            .flat_map(|row| (0..self.size).map(move |col| (row, col)))
+            .filter(|(row, col)| matches!(map[*row][*col].tile_type, Grass | Sand | Hill))
+            .min_by_key(|(row, col)| row.abs_diff(center) + col.abs_diff(center))
+            .unwrap_or((center, center));
+
+        let environmental_conditions = EnvironmentalConditions::new(&vec![Sunny, Rainy, Foggy], 15, 8).unwrap();
+
+        (map, spawn, environmental_conditions, 1000.0, None)
+    }
+}
@@ -1,22 +1,48 @@
 use bevy::prelude::*;
 use crate::world::systems::spawn_map;
 use crate::world::systems::update_map;
+use crate::world::systems::{apply_theme, draw_contours, draw_trails, toggle_elevation_overlay, update_content_badges, update_tile_colors};
+use crate::world::events::TilesUpdated;
+use crate::world::resources::{ElevationOverlay, VisitOverlay, WeatherEffects};
+use crate::world::weather::*;
+use crate::world::chunks::{despawn_chunks, update_chunks};
 use crate::AppState;
 
 pub mod components;
 pub mod systems;
 pub mod resources;
 pub mod generator;
+pub mod coords;
+pub mod events;
+pub mod weather;
+pub mod themes;
+pub mod chunks;
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(OnEnter(AppState::Game), spawn_map)
+            .init_resource::<ElevationOverlay>()
+            .init_resource::<VisitOverlay>()
+            .init_resource::<WeatherEffects>()
+            .add_event::<TilesUpdated>()
+            .add_systems(OnEnter(AppState::Game), (spawn_map, spawn_weather_overlays))
+            .add_systems(OnExit(AppState::Game), (despawn_weather_overlays, despawn_chunks))
             .add_systems(Update, 
                 (
                     update_map,
+                    apply_theme.after(update_map),
+                    update_tile_colors.after(update_map),
+                    // The chunks spawned here are colored next frame, when their tiles exist
+                    update_chunks.after(update_tile_colors).after(apply_theme),
+                    update_content_badges.after(update_map),
+                    toggle_elevation_overlay,
+                    draw_contours,
+                    draw_trails,
+                    update_weather_effects.after(update_map),
+                    update_weather_overlays.after(update_weather_effects),
+                    update_weather_particles.after(update_weather_effects),
                 )
                 .run_if(in_state(AppState::Game))
             );
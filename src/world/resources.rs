@@ -5,19 +5,305 @@ use robotics_lib::interface::Tools;
 use robotics_lib::runner::Robot;
 use std::sync::Arc;
 use robotics_lib::world::tile::Tile;
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Mutex;
+use std::str::FromStr;
+use std::path::PathBuf;
+use rand::Rng;
+use serde::Deserialize;
 use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use crate::player::events::{RobotCommand, RobotEventKind};
+use crate::player::messages::{Coords, QueueDepth, SimMessage};
+use crate::AI::planning::Plan;
+use std::collections::{HashMap, VecDeque};
+use robotics_lib::world::tile::Content;
 
 #[derive(Resource)]
 pub struct WorldRes {
+    // Every tile seen by any robot, in robot coordinates
     pub world: Option<Vec<Vec<Option<Tile>>>>,
-    pub rx: Mutex<Receiver<((Vec<Vec<Option<Tile>>>, (usize, usize)), EnvironmentalConditions, f32)>>,
+    // Tick when each tile was last seen, None if never
+    pub last_seen: Vec<Vec<Option<usize>>>,
+    // How many times a robot moved on each tile, for the heatmap
+    pub visits: Vec<Vec<usize>>,
+    // Lowest and highest elevation among the known tiles, updated by set_tile
+    elevation_range: Option<(usize, usize)>,
+    // The latest tick completed by any robot
+    pub tick: usize,
     pub world_size: usize,
+    pub environmental_conditions: EnvironmentalConditions,
+    // One entry per robot, in the same order as the Runners
+    pub robots: Vec<RobotState>,
+}
+
+// What the ECS knows about one of the robots
+pub struct RobotState {
+    pub name: String,
+    pub color: Color,
+    pub rx: Mutex<Receiver<SimMessage>>,
+    // How many messages are waiting in rx
+    pub queue_depth: QueueDepth,
+    // The most messages drained in a single frame
+    pub peak_drained: usize,
+    // robotics_lib events with their tick, turned into RobotEvent by forward_robot_events
+    pub events_rx: Mutex<Receiver<(usize, RobotEventKind)>>,
+    // Commands from the UI, like the Spotlight button
+    pub commands_tx: Mutex<Sender<RobotCommand>>,
     pub player_x: usize,
     pub player_y: usize,
-    pub environmental_conditions: EnvironmentalConditions,
     pub score: f32,
     pub elevation: usize,
+    pub energy: usize,
+    pub backpack: HashMap<Content, usize>,
+    // What the robot sees in its last tick
+    pub in_view: Vec<Coords>,
+    // Where the robot is going, drawn with gizmos
+    pub plan: Option<Plan>,
+    // The last positions of the robot, oldest first, at most trail_length
+    pub trail: VecDeque<Coords>,
+}
+
+impl WorldRes {
+    pub fn new(world_size: usize, environmental_conditions: EnvironmentalConditions, robots: Vec<RobotState>) -> Self {
+        WorldRes {
+            world: Some(vec![vec![None; world_size]; world_size]),
+            last_seen: vec![vec![None; world_size]; world_size],
+            visits: vec![vec![0; world_size]; world_size],
+            elevation_range: None,
+            tick: 0,
+            world_size,
+            environmental_conditions,
+            robots,
+        }
+    }
+
+    // Lowest and highest elevation among the known tiles
+    pub fn elevation_range(&self) -> Option<(usize, usize)> {
+        self.elevation_range
+    }
+
+    // Stores a tile a robot saw, the elevation range grows with the tiles instead of scanning the map
+    pub fn set_tile(&mut self, (x, y): Coords, tile: Tile) {
+        if x >= self.world_size || y >= self.world_size {
+            return;
+        }
+        let Some(known) = self.world.as_mut() else {
+            return;
+        };
+        let elevation = tile.elevation;
+        known[x][y] = Some(tile);
+        self.elevation_range = match self.elevation_range {
+            None => Some((elevation, elevation)),
+            Some((min, max)) => Some((min.min(elevation), max.max(elevation))),
+        };
+    }
+
+    // Forgets where the robots have been, for the heatmap and the trails
+    pub fn reset_visits(&mut self) {
+        for row in self.visits.iter_mut() {
+            row.fill(0);
+        }
+        for robot in self.robots.iter_mut() {
+            robot.trail.clear();
+        }
+    }
+}
+
+// How elevation is drawn on the map, cycled with the elevation_overlay key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ElevationMode {
+    #[default]
+    Off,
+    // Tiles tinted from blue (low) to red (high)
+    Shading,
+    // A line between tiles in different height bands
+    Contours,
+}
+
+impl ElevationMode {
+    pub fn next(&self) -> Self {
+        match self {
+            ElevationMode::Off => ElevationMode::Shading,
+            ElevationMode::Shading => ElevationMode::Contours,
+            ElevationMode::Contours => ElevationMode::Off,
+        }
+    }
 }
 
+#[derive(Resource, Default)]
+pub struct ElevationOverlay {
+    pub mode: ElevationMode,
+}
+
+// Where the robots have been, both toggled from the bottom bar
+#[derive(Resource, Default)]
+pub struct VisitOverlay {
+    // Tiles tinted by how many times a robot moved on them
+    pub heatmap: bool,
+    // A fading line through the last positions of every robot
+    pub trail: bool,
+}
+
+// How strong each weather effect is right now, between 0 and 1.
+// They follow the environmental conditions slowly, so a change of weather fades in
+#[derive(Resource, Default)]
+pub struct WeatherEffects {
+    pub night: f32,
+    pub rain: f32,
+    // Slants the rain, for the monsoon
+    pub wind: f32,
+    pub snow: f32,
+    pub fog: f32,
+}
+
+// The world sizes that can be picked from the main menu
+pub const WORLD_SIZES: [usize; 6] = [30, 50, 100, 200, 300, 1000];
+
+// The seed used to generate the world, "random" picks a new one every session
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Seed {
+    Random,
+    Fixed(u64),
+}
+
+impl Seed {
+    // Gets the actual seed to give to the generator
+    pub fn resolve(&self) -> u64 {
+        match self {
+            Seed::Random => rand::thread_rng().gen(),
+            Seed::Fixed(seed) => *seed,
+        }
+    }
+}
+
+impl FromStr for Seed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("random") {
+            return Ok(Seed::Random);
+        }
+        s.parse::<u64>()
+            .map(Seed::Fixed)
+            .map_err(|_| format!("invalid seed '{}', expected a number or \"random\"", s))
+    }
+}
+
+// Which contents are spawned in the world and how many of them
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentPreset {
+    // The OxAg default preset
+    Default,
+    // Lots of garbage and bins, the one the brain is trained on
+    Garbage,
+    // Few garbage and bins, the robot has to explore more
+    Sparse,
+}
+
+impl ContentPreset {
+    pub const ALL: [ContentPreset; 3] = [ContentPreset::Default, ContentPreset::Garbage, ContentPreset::Sparse];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentPreset::Default => "default",
+            ContentPreset::Garbage => "garbage",
+            ContentPreset::Sparse => "sparse",
+        }
+    }
+
+    // Used by the main menu to cycle through the presets
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|p| p == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl FromStr for ContentPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|p| p.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| format!("invalid content preset '{}', expected one of: default, garbage, sparse", s))
+    }
+}
+
+// Where the world comes from
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeneratorKind {
+    // The OxAg world generator
+    OxAg,
+    // A world saved to a file, loaded with worldgen_unwrap
+    File,
+    // The generator in world::generator, noise based and fast
+    Procedural,
+}
+
+impl GeneratorKind {
+    pub const ALL: [GeneratorKind; 3] = [GeneratorKind::OxAg, GeneratorKind::File, GeneratorKind::Procedural];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GeneratorKind::OxAg => "oxag",
+            GeneratorKind::File => "file",
+            GeneratorKind::Procedural => "procedural",
+        }
+    }
+
+    // Used by the main menu to cycle through the generators
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|g| g == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl FromStr for GeneratorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|g| g.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| format!("invalid generator '{}', expected one of: oxag, file, procedural", s))
+    }
+}
+
+// The settings used to build the world when the player presses Play
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldSettings {
+    pub generator: GeneratorKind,
+    pub seed: Seed,
+    pub world_size: usize,
+    pub content_preset: ContentPreset,
+    // Only used by the file generator
+    pub world_file: Option<PathBuf>,
+}
+
+impl WorldSettings {
+    // Checks that the chosen generator has everything it needs
+    pub fn validate(&self) -> Result<(), String> {
+        if self.generator == GeneratorKind::File && self.world_file.is_none() {
+            return Err("the file generator needs a world file".to_string());
+        }
+        if self.world_size == 0 {
+            return Err("the world size must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for WorldSettings {
+    fn default() -> Self {
+        WorldSettings {
+            generator: GeneratorKind::OxAg,
+            seed: Seed::Fixed(421),
+            world_size: 300,
+            content_preset: ContentPreset::Garbage,
+            world_file: None,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
+use bevy::sprite::Anchor;
 use bevy_ecs_tilemap::prelude::get_tilemap_center_transform;
 use bevy_ecs_tilemap::prelude::TilemapTexture;
 use bevy_ecs_tilemap::TilemapBundle;
@@ -11,151 +12,69 @@ use bevy_ecs_tilemap::prelude::TilemapId;
 use bevy_ecs_tilemap::prelude::TilemapTileSize;
 use bevy_ecs_tilemap::prelude::TilemapType;
 use bevy_ecs_tilemap::tiles::TileTextureIndex;
+use bevy_ecs_tilemap::tiles::AnimatedTile;
 
 use robotics_lib::world::tile::Tile;
 use robotics_lib::world::tile::TileType;
 use robotics_lib::world::tile::Content;
 use robotics_lib::interface::robot_map;
 use bevy::prelude::Resource;
-use crate::world::resources::WorldRes;
+use crate::world::resources::{ElevationMode, ElevationOverlay, VisitOverlay, WorldRes};
+use crate::world::coords::{robot_to_tile_pos, robot_to_world, TILE_SIZE};
+use crate::player::messages::{Coords, SimMessage};
+use std::collections::{HashMap, HashSet};
+use crate::config::{KeyBindings, RenderConfig};
+use bevy_ecs_tilemap::tiles::TileColor;
 use robotics_lib::runner::Runnable;
 use crate::world::components::TileDraw;
 use crate::world::components::GridDraw;
 use crate::world::components::ContentDraw;
+use crate::world::components::ContentBadge;
+use crate::world::events::TilesUpdated;
+use crate::world::themes::{Atlas, Themes};
+use crate::world::chunks::Chunks;
+use crate::world::coords::chunk_of;
 use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
 
+const CONTOUR_COLOR: Color = Color::rgba(0.1, 0.05, 0.0, 0.8);
+
+// The tilemaps are spawned a chunk at a time by update_chunks, once something in them is known.
+// Here the chunks are set up with what the robots already know
 pub fn spawn_map(
     mut commands: Commands,
-    mut world: ResMut<WorldRes>,
-    //rendered_map: Query<Entity, (With<TileDraw>, With<GridDraw>)>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    world: Res<WorldRes>,
     asset_server: Res<AssetServer>,
+    themes: Res<Themes>,
     #[cfg(all(not(feature = "atlas"), feature = "render"))]
     array_texture_loader: Res<ArrayTextureLoader,>,
 ) {
-
-    // 
-    // DRAW THE TILES ----------------------------------------
-    //
-
-    let window = window_query.get_single().unwrap();
-    let texture_handle: Handle<Image> = asset_server.load("tiles4.png");
-
-    // Create a tilemap entity a little early.
-    // We want this entity early because we need to tell each tile which tilemap entity
-    // it is associated with. This is done with the TilemapId component on each tile.
-    // Eventually, we will insert the `TilemapBundle` bundle on the entity, which
-    // will contain various necessary components, such as `TileStorage`.
-    let tilemap_entity = commands.spawn_empty().id();
-
-    let map_size = world.world_size;
-    let map_size = TilemapSize { x: map_size as u32, y: map_size as u32};
-
-    // To begin creating the map we will need a `TileStorage` component.
-    // This component is a grid of tile entities and is used to help keep track of individual
-    // tiles in the world. If you have multiple layers of tiles you would have a tilemap entity
-    // per layer, each with their own `TileStorage` component.
-    let mut tile_storage = TileStorage::empty(map_size);
-
-    // Spawn empty map 
-    for x in 0..map_size.x {
-        for y in 0..map_size.y {
-            let tile_pos = TilePos { x, y };
-            let tile_entity = commands
-                .spawn((
-                    TileBundle {
-                    position: tile_pos,
-                    tilemap_id: TilemapId(tilemap_entity),
-                    texture_index: TileTextureIndex(12), // Here the None texture index 
-                    ..Default::default()
-                },
-                TileDraw{}))
-                .id();
-            tile_storage.set(&tile_pos, tile_entity);
-        }
-    }
-
-
-    let tile_size = TilemapTileSize { x: 16.0, y: 16.0 };
-    let grid_size = tile_size.into();
-    let map_type = TilemapType::default();
-    
-    // Spawn new map
-    commands.entity(tilemap_entity).insert((TilemapBundle {
-        grid_size,
-        map_type,
-        size: map_size,
-        storage: tile_storage,
-        texture: TilemapTexture::Single(texture_handle),
-        tile_size,
-        transform: get_tilemap_center_transform(&map_size, &grid_size, &map_type, 0.0),
-        ..Default::default()
-    },
-    GridDraw{}));
-
-    //
-    // DRAW THE CONTENT --------------------------------------
-    //
-
-    let texture_handle: Handle<Image> = asset_server.load("contents.png");
-    let tilemap_entity = commands.spawn_empty().id();
-    let map_size = world.world_size;
-    let map_size = TilemapSize { x: map_size as u32, y: map_size as u32};
-    let mut tile_storage = TileStorage::empty(map_size);
-
-    // Spawn empty map 
-    for x in 0..map_size.x {
-        for y in 0..map_size.y {
-            let tile_pos = TilePos { x, y };
-            let tile_entity = commands
-                .spawn((
-                    TileBundle {
-                    position: tile_pos,
-                    tilemap_id: TilemapId(tilemap_entity),
-                    texture_index: TileTextureIndex(15), // Here the None texture index 
-                    ..Default::default()
-                },
-                TileDraw{}))
-                .id();
-            tile_storage.set(&tile_pos, tile_entity);
+    let mut chunks = Chunks::new(world.world_size);
+    if let Some(known) = world.world.as_ref() {
+        for (x, row) in known.iter().enumerate() {
+            for (y, tile) in row.iter().enumerate() {
+                if tile.is_some() {
+                    chunks.reveal((x, y));
+                }
+            }
         }
     }
-
-
-    let tile_size = TilemapTileSize { x: 16.0, y: 16.0 };
-    let grid_size = tile_size.into();
-    let map_type = TilemapType::default();
-   
-    // Spawn new map
-    commands.entity(tilemap_entity).insert((TilemapBundle {
-        grid_size,
-        map_type,
-        size: map_size,
-        storage: tile_storage,
-        texture: TilemapTexture::Single(texture_handle),
-        tile_size,
-        transform: get_tilemap_center_transform(&map_size, &grid_size, &map_type, 1.0),
-        ..Default::default()
-    },
-    ContentDraw{}));
-
+    commands.insert_resource(chunks);
 
     // Add atlas to array texture loader so it's preprocessed before we need to use it.
     // Only used when the atlas feature is off and we are using array textures.
     #[cfg(all(not(feature = "atlas"), feature = "render"))]
     {
-        array_texture_loader.add(TilemapArrayTexture {
-            texture: TilemapTexture::Single(asset_server.load("tiles2.png")),
-            tile_size,
-            ..Default::default()
-        });
-
-        array_texture_loader.add(TilemapArrayTexture {
-            texture: TilemapTexture::Single(asset_server.load("contents.png")),
-            tile_size,
-            ..Default::default()
-        });
-
+        let tile_size = TilemapTileSize { x: TILE_SIZE, y: TILE_SIZE };
+        // Every theme, so switching is instant
+        for atlas in themes.atlases.iter() {
+            for image in [&atlas.tiles, &atlas.contents] {
+                array_texture_loader.add(TilemapArrayTexture {
+                    texture: TilemapTexture::Single(asset_server.load(image)),
+                    tile_size,
+                    ..Default::default()
+                });
+            }
+        }
     }
 }
 
@@ -163,610 +82,401 @@ pub fn spawn_map(
 pub fn update_map(
     mut commands: Commands,
     mut world: ResMut<WorldRes>,
-    // Tiles Map
-    mut tile_storage: Query<&mut TileStorage, (Without<ContentDraw>, With<GridDraw>)>,
-    mut tilemap_entity_query: Query<Entity, (With<GridDraw>, Without<ContentDraw>)>,
-    //rendered_map: Query<Entity, (With<TileDraw>, With<GridDraw>)>,
-    // Content Map
-    mut content_storage: Query<&mut TileStorage, (Without<GridDraw>, With<ContentDraw>)>,
-    mut content_entity_query: Query<Entity, (With<ContentDraw>, Without<GridDraw>)>,
-    //rendered_content: Query<Entity, (With<TileDraw>, With<ContentDraw>, Without<GridDraw>)>,
-    // Other Queries
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
-    #[cfg(all(not(feature = "atlas"), feature = "render"))]
-    array_texture_loader: Res<ArrayTextureLoader,>,
+    mut chunks: ResMut<Chunks>,
+    // The tiles of both layers of every chunk
+    mut textures: Query<&mut TileTextureIndex>,
+    render_config: Res<RenderConfig>,
+    themes: Res<Themes>,
+    mut tiles_updated: EventWriter<TilesUpdated>,
 ) {
-        
-
-    // Recieve the map or return
-    let res = match world.rx.lock().unwrap().try_recv() {
-         Ok(w) => Some(w),
-         Err(_) => None,
-    };
-    if let None = res {
-        return;
-    }
-    let ((map, (robot_x, robot_y)), environmental_conditions, score) = res.unwrap();
     let map_size = world.world_size;
-    world.environmental_conditions = environmental_conditions;
-    world.score = score;
-    world.elevation = map[1][1].clone().unwrap().elevation; 
-    //
-    // Draw Tiles ---------------------------------------------
-    //
-
-    let mut tile_map = &mut tile_storage.get_single_mut().unwrap();
-    let tilemap_entity = tilemap_entity_query.get_single().unwrap();
-
-    // Doing this for all nine tiles
-   // Remember that the robot coordinates as swapped
-   // ROBOT 
-   // ------------------> y 
-   // |
-   // |
-   // |
-   // |
-   // |
-   // V 
-   // x
-   //
-   // WORLD 
-   // y 
-   // ^
-   // |
-   // |
-   // |
-   // |
-   // |
-   // |
-   // -------------------> x 
 
-    // TOP LEFT
-    if is_inside(robot_x as i32 - 1, robot_y as i32 - 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 - 1,
-            y:  map_size as u32 -1 - robot_x as u32 + 1,
-        };
-        let new_tile_id = get_texture_id(&map[0][0]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
-    }
- 
-    // TOP
-    if is_inside(robot_x as i32 - 1, robot_y as i32, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32,
-            y:  map_size as u32 -1 - robot_x as u32 + 1,
-        };
-        let new_tile_id = get_texture_id(&map[0][1]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
+    // Everything that arrived since the last frame is read, so the map never falls behind the robots.
+    // A tile revealed many times is drawn once, with its last value
+    let mut revealed: HashMap<Coords, Tile> = HashMap::new();
+    let mut weather = None;
+    // Tiles seen by the robots and the tick they were seen in
+    let mut seen: Vec<(Coords, usize)> = Vec::new();
+    let mut latest_tick = world.tick;
+    // Every position update is a visit, for the heatmap
+    let mut moves: Vec<Coords> = Vec::new();
+    for robot in world.robots.iter_mut() {
+        let rx = robot.rx.lock().unwrap();
+        let mut drained = 0;
+        // The tick of these is only known at TickCompleted
+        let mut seen_this_tick: Vec<Coords> = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            drained += 1;
+            match message {
+                SimMessage::TilesRevealed(tiles) => {
+                    seen_this_tick.extend(tiles.iter().map(|(coords, _)| *coords));
+                    revealed.extend(tiles);
+                }
+                SimMessage::RobotMoved((x, y), elevation) => {
+                    robot.player_x = x;
+                    robot.player_y = y;
+                    robot.elevation = elevation;
+                    moves.push((x, y));
+                    robot.trail.push_back((x, y));
+                    while robot.trail.len() > render_config.trail_length {
+                        robot.trail.pop_front();
+                    }
+                }
+                SimMessage::EnergyChanged(energy) => robot.energy = energy,
+                SimMessage::BackpackChanged(backpack) => robot.backpack = backpack,
+                SimMessage::WeatherChanged(conditions) => weather = Some(conditions),
+                SimMessage::ScoreChanged(score) => robot.score = score,
+                SimMessage::PlanChanged(plan) => robot.plan = plan,
+                SimMessage::InView(in_view) => {
+                    seen_this_tick.extend(in_view.iter().copied());
+                    robot.in_view = in_view;
+                }
+                SimMessage::TickCompleted(tick) => {
+                    seen.extend(seen_this_tick.drain(..).map(|coords| (coords, tick)));
+                    latest_tick = latest_tick.max(tick);
+                }
+            }
         }
+        robot.queue_depth.received(drained);
+        robot.peak_drained = robot.peak_drained.max(drained);
     }
-      
-    
-    // TOP RIGHT
-    if is_inside(robot_x as i32 - 1, robot_y as i32 + 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 + 1,
-            y:  map_size as u32 -1 - robot_x as u32 + 1,
-        };
-        let new_tile_id = get_texture_id(&map[0][2]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
+    if let Some(conditions) = weather {
+        world.environmental_conditions = conditions;
     }
 
-
-    // BOTTOM LEFT
-    if is_inside(robot_x as i32 + 1, robot_y as i32 - 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 - 1,
-            y:  map_size as u32 -1- robot_x as u32 - 1,
-        };
-        let new_tile_id = get_texture_id(&map[2][0]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
+    // What the robots know, for the fog and anything else that needs the tiles
+    world.tick = latest_tick;
+    for ((x, y), tick) in seen {
+        if x < map_size && y < map_size {
+            let last_seen = &mut world.last_seen[x][y];
+            *last_seen = Some(last_seen.map_or(tick, |last| last.max(tick)));
         }
     }
- 
-    // BOTTOM
-    if is_inside(robot_x as i32 + 1, robot_y as i32, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32,
-            y:  map_size as u32 -1- robot_x as u32 - 1,
-        };
-        let new_tile_id = get_texture_id(&map[2][1]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
+    for (x, y) in moves {
+        if x < map_size && y < map_size {
+            world.visits[x][y] += 1;
         }
     }
-      
-    
-    // BOTTOM RIGHT
-    if is_inside(robot_x as i32 + 1, robot_y as i32 + 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 + 1,
-            y:  map_size as u32 -1- robot_x as u32 - 1,
-        };
-        let new_tile_id = get_texture_id(&map[2][2]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
+    for (coords, tile) in revealed.iter() {
+        world.set_tile(*coords, tile.clone());
     }
 
-
-    // MIDDLE LEFT
-    if is_inside(robot_x as i32, robot_y as i32 - 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 - 1,
-            y: map_size as u32-1 - robot_x as u32,
-
-        };
-        let new_tile_id = get_texture_id(&map[1][0]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
+    // All the robots share the same seed, so the map is drawn with what any of them saw.
+    // The chunks not spawned yet are drawn from WorldRes when they are
+    for coords in revealed.keys() {
+        chunks.reveal(*coords);
     }
-      
-    
-    // MIDDLE RIGHT
-    if is_inside(robot_x as i32, robot_y as i32 + 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 + 1,
-            y: map_size as u32 -1- robot_x as u32,
-        };
-        let new_tile_id = get_texture_id(&map[1][2]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
+    draw_tiles(&revealed, &chunks, &mut textures, &mut commands, themes.current());
+    if !revealed.is_empty() {
+        tiles_updated.send(TilesUpdated(revealed.into_keys().collect()));
     }
-    
-    // MIDDLE
-    if is_inside(robot_x as i32, robot_y as i32, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32,
-            y: map_size as u32 -1- robot_x as u32,
+}
+
+// Keeps the badges of the spawned chunks in sync with the updated tiles: one on every known tile
+// with more than one of its content, or with something in it for the containers.
+// The other chunks get theirs when they are spawned
+pub fn update_content_badges(
+    mut commands: Commands,
+    mut tiles_updated: EventReader<TilesUpdated>,
+    world: Res<WorldRes>,
+    mut chunks: ResMut<Chunks>,
+    asset_server: Res<AssetServer>,
+    mut texts: Query<&mut Text, With<ContentBadge>>,
+) {
+    let Some(known) = world.world.as_ref() else {
+        return;
+    };
+    let size = world.world_size;
+    // The badges don't change the tiles, update_tile_colors doesn't need to run again
+    let chunks = chunks.bypass_change_detection();
+    for (x, y) in tiles_updated.iter().flat_map(|updated| updated.0.iter().copied()) {
+        let Some(loaded) = chunks.loaded.get_mut(&chunk_of((x, y))) else {
+            continue;
         };
-        let new_tile_id = get_texture_id(&map[1][1]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
+        let label = known.get(x).and_then(|row| row.get(y)).and_then(|tile| tile.as_ref()).and_then(|tile| content_badge(&tile.content));
+        match (label, loaded.badges.get(&(x, y)).copied()) {
+            (Some(label), Some(entity)) => {
+                if let Ok(mut text) = texts.get_mut(entity) {
+                    text.sections[0].value = label;
+                }
+            }
+            (Some(label), None) => {
+                let entity = spawn_content_badge(&mut commands, &asset_server, label, (x, y), size);
+                loaded.badges.insert((x, y), entity);
+            }
+            (None, Some(entity)) => {
+                commands.entity(entity).despawn();
+                loaded.badges.remove(&(x, y));
+            }
+            (None, None) => {}
         }
     }
+}
 
-    
-    //
-    // Draw Content ---------------------------------------------
-    //
-
-    let mut tile_map = &mut content_storage.get_single_mut().unwrap();
-    let tilemap_entity = content_entity_query.get_single().unwrap();
+// A badge in the bottom right corner of the tile
+pub fn spawn_content_badge(commands: &mut Commands, asset_server: &AssetServer, label: String, coords: Coords, size: usize) -> Entity {
+    let position = robot_to_world(coords, size) + Vec2::new(TILE_SIZE / 2.0 - 1.0, -TILE_SIZE / 2.0 + 1.0);
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(label, TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 7.0,
+                color: Color::WHITE,
+            }),
+            text_anchor: Anchor::BottomRight,
+            // Above the content, under the robots
+            transform: Transform::from_translation(position.extend(1.5)),
+            ..default()
+        },
+        ContentBadge{},
+    )).id()
+}
 
-    // TOP LEFT
-    if is_inside(robot_x as i32 - 1, robot_y as i32 - 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 - 1,
-            y:  map_size as u32 -1 - robot_x as u32 + 1,
-        };
-        let new_tile_id = get_texture_id_content(&map[0][0]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
+// What the badge of a content says, None when there's nothing worth showing
+pub fn content_badge(content: &Content) -> Option<String> {
+    match content {
+        // The containers show how full they are
+        Content::Bin(range) | Content::Crate(range) | Content::Bank(range) => {
+            Some(format!("{}/{}", range.start, range.end))
         }
+        Content::Rock(n) | Content::Tree(n) | Content::Garbage(n) | Content::Coin(n)
+        | Content::Water(n) | Content::Fish(n) | Content::Bush(n) | Content::JollyBlock(n)
+        | Content::Market(n) if *n > 1 => Some(n.to_string()),
+        _ => None,
     }
- 
-    // TOP
-    if is_inside(robot_x as i32 - 1, robot_y as i32, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32,
-            y:  map_size as u32 -1 - robot_x as u32 + 1,
-        };
-        let new_tile_id = get_texture_id_content(&map[0][1]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
-    }
-      
-    
-    // TOP RIGHT
-    if is_inside(robot_x as i32 - 1, robot_y as i32 + 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 + 1,
-            y:  map_size as u32 -1 - robot_x as u32 + 1,
+}
+
+// Changes the texture of every updated tile in place, the updates can come from a view of any size.
+// Also adds the animation of the tiles that have one, and removes it from the ones that stopped
+// having one, like a fire that went out
+pub fn draw_tiles<'a>(
+    updates: impl IntoIterator<Item = (&'a Coords, &'a Tile)>,
+    chunks: &Chunks,
+    textures: &mut Query<&mut TileTextureIndex>,
+    commands: &mut Commands,
+    atlas: &Atlas,
+) {
+    for (coords, tile) in updates {
+        let Some((grid_entity, content_entity)) = chunks.tile_entities(*coords) else {
+            continue;
         };
-        let new_tile_id = get_texture_id_content(&map[0][2]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
+        let layers = [
+            (grid_entity, atlas.tile_index(tile), atlas.tile_animation(tile)),
+            (content_entity, atlas.content_index(tile), atlas.content_animation(tile)),
+        ];
+        for (tile_entity, new_id, animation) in layers {
+            if let Ok(mut texture_index) = textures.get_mut(tile_entity) {
+                // Avoids marking unchanged tiles as changed
+                if texture_index.0 != new_id {
+                    texture_index.0 = new_id;
+                }
+            }
+            match animation {
+                Some(animated) => commands.entity(tile_entity).insert(animated),
+                None => commands.entity(tile_entity).remove::<AnimatedTile>(),
+            };
         }
     }
+}
 
-
-    // BOTTOM LEFT
-    if is_inside(robot_x as i32 + 1, robot_y as i32 - 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 - 1,
-            y:  map_size as u32 -1- robot_x as u32 - 1,
-        };
-        let new_tile_id = get_texture_id_content(&map[2][0]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
+// Redraws the spawned chunks with the new theme when it changes, the tiles stay where they are.
+// The others are spawned with the new one
+pub fn apply_theme(
+    mut commands: Commands,
+    themes: Res<Themes>,
+    world: Res<WorldRes>,
+    chunks: Res<Chunks>,
+    asset_server: Res<AssetServer>,
+    mut tilemaps: Query<(&mut TilemapTexture, Option<&GridDraw>)>,
+    mut textures: Query<&mut TileTextureIndex>,
+) {
+    // The chunks already use the theme it starts with
+    if !themes.is_changed() || themes.is_added() {
+        return;
     }
- 
-    // BOTTOM
-    if is_inside(robot_x as i32 + 1, robot_y as i32, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32,
-            y:  map_size as u32 -1- robot_x as u32 - 1,
-        };
-        let new_tile_id = get_texture_id_content(&map[2][1]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
+    let Some(known) = world.world.as_ref() else {
+        return;
+    };
+    let atlas = themes.current();
+    for (mut texture, grid) in tilemaps.iter_mut() {
+        let image = if grid.is_some() { &atlas.tiles } else { &atlas.contents };
+        *texture = TilemapTexture::Single(asset_server.load(image));
     }
-      
-    
-    // BOTTOM RIGHT
-    if is_inside(robot_x as i32 + 1, robot_y as i32 + 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 + 1,
-            y:  map_size as u32 -1- robot_x as u32 - 1,
+
+    for (x, y) in chunks.loaded_tiles() {
+        let Some((grid_entity, content_entity)) = chunks.tile_entities((x, y)) else {
+            continue;
         };
-        let new_tile_id = get_texture_id_content(&map[2][2]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
+        let layers = match &known[x][y] {
+            Some(tile) => [
+                (grid_entity, atlas.tile_index(tile), atlas.tile_animation(tile)),
+                (content_entity, atlas.content_index(tile), atlas.content_animation(tile)),
+            ],
+            None => [(grid_entity, atlas.unknown_tile, None), (content_entity, atlas.no_content, None)],
+        };
+        for (tile_entity, index, animation) in layers {
+            if let Ok(mut texture_index) = textures.get_mut(tile_entity) {
+                texture_index.0 = index;
+            }
+            match animation {
+                Some(animated) => commands.entity(tile_entity).insert(animated),
+                None => commands.entity(tile_entity).remove::<AnimatedTile>(),
+            };
         }
     }
+}
 
+// Colors the known tiles: the fog dims the ones the robots only remember, by how long ago they were
+// seen, and the elevation shading or the heatmap tint them.
+// Runs only when a tick completes, an overlay changes or chunks are spawned, nothing changes in between.
+// Only the spawned chunks are colored, the others have nothing to color
+pub fn update_tile_colors(
+    world: Res<WorldRes>,
+    chunks: Res<Chunks>,
+    render_config: Res<RenderConfig>,
+    elevation_overlay: Res<ElevationOverlay>,
+    visit_overlay: Res<VisitOverlay>,
+    mut colors: Query<&mut TileColor>,
+    mut last_tick: Local<Option<usize>>,
+) {
+    if *last_tick == Some(world.tick) && !elevation_overlay.is_changed() && !visit_overlay.is_changed() && !chunks.is_changed() {
+        return;
+    }
+    *last_tick = Some(world.tick);
 
-    // MIDDLE LEFT
-    if is_inside(robot_x as i32, robot_y as i32 - 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 - 1,
-            y: map_size as u32-1 - robot_x as u32,
+    let Some(known) = world.world.as_ref() else {
+        return;
+    };
+    let in_view: HashSet<Coords> = world.robots.iter().flat_map(|robot| robot.in_view.iter().copied()).collect();
+    // The heatmap hides the elevation shading, both on the same tiles would be unreadable
+    let max_visits = if visit_overlay.heatmap { world.visits.iter().flatten().copied().max().unwrap_or(0) } else { 0 };
+    let shading = match elevation_overlay.mode {
+        ElevationMode::Shading => world.elevation_range(),
+        _ => None,
+    };
 
+    for (x, y) in chunks.loaded_tiles() {
+        // Never seen tiles keep the None texture
+        let Some(last_seen) = &world.last_seen[x][y] else {
+            continue;
         };
-        let new_tile_id = get_texture_id_content(&map[1][0]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
-    }
-      
-    
-    // MIDDLE RIGHT
-    if is_inside(robot_x as i32, robot_y as i32 + 1, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32 + 1,
-            y: map_size as u32 -1- robot_x as u32,
+        let brightness = if in_view.contains(&(x, y)) {
+            1.0
+        } else {
+            fog_brightness(world.tick.saturating_sub(*last_seen), &render_config)
         };
-        let new_tile_id = get_texture_id_content(&map[1][2]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
-        }
-    }
-    
-    // MIDDLE
-    if is_inside(robot_x as i32, robot_y as i32, map_size as i32) {
-        // World coordinates
-        let position = TilePos {
-            x: robot_y as u32,
-            y: map_size as u32 -1- robot_x as u32,
+        let Some((grid_entity, content_entity)) = chunks.tile_entities((x, y)) else {
+            continue;
+        };
+
+        let tint = match (shading, &known[x][y]) {
+            _ if visit_overlay.heatmap => heat_color(world.visits[x][y], max_visits),
+            (Some((min, max)), Some(tile)) => elevation_color(tile.elevation, min, max),
+            _ => Color::WHITE,
         };
-        let new_tile_id = get_texture_id_content(&map[1][1]);
-        if let Some(old_tile) = tile_map.get(&position) {
-            // Despawn old tile 
-            commands.entity(old_tile).despawn();
-            // Spawn a new tile
-            let tile_entity = commands
-                    .spawn((
-                        TileBundle {
-                        position: position,
-                        tilemap_id: TilemapId(tilemap_entity),
-                        texture_index: TileTextureIndex(new_tile_id),
-                        ..Default::default()
-                    },
-                    TileDraw{}))
-                    .id();
-                tile_map.set(&position, tile_entity);
+        let color = Color::rgb(tint.r() * brightness, tint.g() * brightness, tint.b() * brightness);
+        // The content layer is not tinted, or it would be hard to tell what it is
+        let content_color = Color::rgb(brightness, brightness, brightness);
+
+        for (tile_entity, color) in [(grid_entity, color), (content_entity, content_color)] {
+            if let Ok(mut tile_color) = colors.get_mut(tile_entity) {
+                if tile_color.0 != color {
+                    tile_color.0 = color;
+                }
+            }
         }
     }
+}
 
+// Blue for the lowest known tiles, red for the highest
+pub fn elevation_color(elevation: usize, min: usize, max: usize) -> Color {
+    let t = if max > min { (elevation - min) as f32 / (max - min) as f32 } else { 0.0 };
+    Color::rgb(0.3 + 0.7 * t, 0.5, 1.0 - 0.7 * t)
+}
 
+// Untouched for tiles never visited, then from yellow to red for the most visited one.
+// Logarithmic, or a single tile the robot keeps coming back to would make the rest look the same
+pub fn heat_color(visits: usize, max: usize) -> Color {
+    if visits == 0 || max == 0 {
+        return Color::WHITE;
+    }
+    let t = (visits as f32).ln_1p() / (max as f32).ln_1p();
+    Color::rgb(1.0, 0.9 * (1.0 - t), 0.2 * (1.0 - t))
 }
 
-// Using robot coordinates
-fn is_inside(x: i32, y: i32, map_size: i32) -> bool {
-    x >= 0 && y >= 0 && y < map_size && x < map_size 
+// A fading line through the last positions of every robot, in its color
+pub fn draw_trails(
+    world: Res<WorldRes>,
+    visit_overlay: Res<VisitOverlay>,
+    mut gizmos: Gizmos,
+) {
+    if !visit_overlay.trail {
+        return;
+    }
+    let size = world.world_size;
+    for robot in world.robots.iter() {
+        let steps = robot.trail.len();
+        for (i, (from, to)) in robot.trail.iter().zip(robot.trail.iter().skip(1)).enumerate() {
+            // The oldest step is almost transparent
+            let alpha = (i + 1) as f32 / steps as f32;
+            gizmos.line_2d(robot_to_world(*from, size), robot_to_world(*to, size), robot.color.with_a(alpha));
+        }
+    }
 }
 
-// Gets the tilemap id given a TyleType 
-fn get_texture_id(tile: &Option<Tile>) -> u32 {
-    match tile {         
-        Some(tile) => {
-            match tile.tile_type {
-                TileType::DeepWater => 0,
-                TileType::ShallowWater => 1,
-                TileType::Sand => 2,
-                TileType::Grass => 3,
-                TileType::Street => 4,
-                TileType::Hill => 5,
-                TileType::Mountain => 7,
-                TileType::Snow => 8,
-                TileType::Lava => 9,
-                TileType::Teleport(_) => 10,
-                TileType::Wall => 11,
-                _ => 12,
+// A line on the edge between two neighbouring tiles in different height bands.
+// Only on the spawned chunks, the rest of the map is off screen
+pub fn draw_contours(
+    world: Res<WorldRes>,
+    chunks: Res<Chunks>,
+    render_config: Res<RenderConfig>,
+    elevation_overlay: Res<ElevationOverlay>,
+    mut gizmos: Gizmos,
+) {
+    if elevation_overlay.mode != ElevationMode::Contours {
+        return;
+    }
+    let Some(known) = world.world.as_ref() else {
+        return;
+    };
+    let size = world.world_size;
+    let band = |tile: &Tile| tile.elevation / render_config.contour_interval;
+    let half = TILE_SIZE / 2.0;
+
+    for (x, y) in chunks.loaded_tiles() {
+        let Some(tile) = &known[x][y] else {
+            continue;
+        };
+        let center = robot_to_world((x, y), size);
+        // Right neighbour: vertical edge
+        if let Some(Some(right)) = known[x].get(y + 1) {
+            if band(tile) != band(right) {
+                gizmos.line_2d(center + Vec2::new(half, -half), center + Vec2::new(half, half), CONTOUR_COLOR);
             }
-        },
-        None => 12,
+        }
+        // Neighbour below: horizontal edge
+        if let Some(Some(below)) = known.get(x + 1).map(|row| &row[y]) {
+            if band(tile) != band(below) {
+                gizmos.line_2d(center + Vec2::new(-half, -half), center + Vec2::new(half, -half), CONTOUR_COLOR);
+            }
+        }
     }
-    
 }
 
-// Gets the tilemap id given a TyleType for the content 
-fn get_texture_id_content(tile: &Option<Tile>) -> u32 {
-    match tile {         
-        Some(tile) => {
-            match tile.content {
-                Content::Rock(_) => 0,
-                Content::Tree(_) => 1,
-                Content::Garbage(_) => 2,
-                Content::Fire => 3,
-                Content::Coin(_) => 4,
-                Content::Bin(_) => 5,
-                Content::Crate(_) => 5,
-                Content::Bank(_) => 6,
-                Content::Water(_) => 7,
-                Content::Market(_) => 8,
-                Content::Fish(_) => 9,
-                Content::Building => 10,
-                Content::Bush(_) => 11,
-                Content::JollyBlock(_) => 12,
-                Content::Scarecrow => 13,
-                Content::None => 14,
-                _ => 14,
-            }
-        },
-        None => 14,
+pub fn toggle_elevation_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    mut elevation_overlay: ResMut<ElevationOverlay>,
+) {
+    if key_bindings.elevation_overlay.just_pressed(&keyboard_input) {
+        elevation_overlay.mode = elevation_overlay.mode.next();
     }
-    
+}
+
+// From fog_max_brightness for what was seen last tick, down to fog_min_brightness after fog_fade_ticks.
+// Even a tile seen one tick ago is not in view anymore, so it starts darker than the visible ones
+fn fog_brightness(age: usize, render_config: &RenderConfig) -> f32 {
+    let max = render_config.fog_max_brightness;
+    let faded = (age as f32 / render_config.fog_fade_ticks as f32).min(1.0);
+    max - (max - render_config.fog_min_brightness) * faded
 }
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::FileAssetIo;
+use bevy::prelude::*;
+use bevy_ecs_tilemap::tiles::AnimatedTile;
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use serde::Deserialize;
+
+use crate::world::coords::TILE_SIZE;
+
+// Where the atlas descriptors are, one .ron file per theme, see themes_dir
+pub const THEMES_DIR: &str = "assets/themes";
+
+const TILE_TYPE_NAMES: [&str; 11] = [
+    "DeepWater", "ShallowWater", "Sand", "Grass", "Street", "Hill", "Mountain", "Snow", "Lava", "Teleport", "Wall",
+];
+const CONTENT_NAMES: [&str; 15] = [
+    "Rock", "Tree", "Garbage", "Fire", "Coin", "Bin", "Crate", "Bank", "Water", "Market", "Fish", "Building",
+    "Bush", "JollyBlock", "Scarecrow",
+];
+
+// Frames of an animated tile, the end is excluded
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Animation {
+    pub start: u32,
+    pub end: u32,
+    pub speed: f32,
+}
+
+// Which frame of the two images draws every TileType and Content
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Atlas {
+    pub name: String,
+    // Images in the assets folder, made of 16x16 frames side by side
+    pub tiles: String,
+    pub contents: String,
+    pub tile_types: HashMap<String, u32>,
+    // Tiles never seen, and the types missing from tile_types
+    pub unknown_tile: u32,
+    pub content_kinds: HashMap<String, u32>,
+    // Tiles without content, and the contents missing from content_kinds
+    pub no_content: u32,
+    #[serde(default)]
+    pub tile_animations: HashMap<String, Animation>,
+    #[serde(default)]
+    pub content_animations: HashMap<String, Animation>,
+}
+
+impl Atlas {
+    // A typo in a name would silently draw the unknown frame, so it's an error
+    fn validate(&self) -> Result<(), String> {
+        for name in self.tile_types.keys().chain(self.tile_animations.keys()) {
+            if !TILE_TYPE_NAMES.contains(&name.as_str()) {
+                return Err(format!("unknown tile type '{}'", name));
+            }
+        }
+        for name in self.content_kinds.keys().chain(self.content_animations.keys()) {
+            if !CONTENT_NAMES.contains(&name.as_str()) {
+                return Err(format!("unknown content '{}'", name));
+            }
+        }
+        for (name, animation) in self.tile_animations.iter().chain(self.content_animations.iter()) {
+            if animation.end <= animation.start || animation.speed.is_nan() || animation.speed <= 0.0 {
+                return Err(format!("the animation of '{}' needs start < end and a positive speed", name));
+            }
+        }
+        Ok(())
+    }
+
+    // Every frame has to be in its image, or the tile would be drawn with whatever is past its end.
+    // The images are only loaded later by the AssetServer, so the frame counts come from their headers
+    fn check_frames(&self, tile_frames: u32, content_frames: u32) -> Result<(), String> {
+        let check = |key: String, frame: u32, image: &str, frames: u32| {
+            if frame < frames {
+                Ok(())
+            } else {
+                Err(format!("{} needs frame {}, but '{}' only has {} frames", key, frame, image, frames))
+            }
+        };
+
+        check("unknown_tile".to_string(), self.unknown_tile, &self.tiles, tile_frames)?;
+        for (name, frame) in self.tile_types.iter() {
+            check(format!("tile_types.{}", name), *frame, &self.tiles, tile_frames)?;
+        }
+        for (name, animation) in self.tile_animations.iter() {
+            // The end is excluded
+            check(format!("tile_animations.{}.end", name), animation.end - 1, &self.tiles, tile_frames)?;
+        }
+
+        check("no_content".to_string(), self.no_content, &self.contents, content_frames)?;
+        for (name, frame) in self.content_kinds.iter() {
+            check(format!("content_kinds.{}", name), *frame, &self.contents, content_frames)?;
+        }
+        for (name, animation) in self.content_animations.iter() {
+            check(format!("content_animations.{}.end", name), animation.end - 1, &self.contents, content_frames)?;
+        }
+        Ok(())
+    }
+
+    pub fn tile_index(&self, tile: &Tile) -> u32 {
+        self.tile_types.get(tile_type_name(&tile.tile_type)).copied().unwrap_or(self.unknown_tile)
+    }
+
+    pub fn content_index(&self, tile: &Tile) -> u32 {
+        self.content_kinds.get(content_name(&tile.content)).copied().unwrap_or(self.no_content)
+    }
+
+    pub fn tile_animation(&self, tile: &Tile) -> Option<AnimatedTile> {
+        self.tile_animations.get(tile_type_name(&tile.tile_type)).map(to_animated_tile)
+    }
+
+    pub fn content_animation(&self, tile: &Tile) -> Option<AnimatedTile> {
+        self.content_animations.get(content_name(&tile.content)).map(to_animated_tile)
+    }
+}
+
+// How many 16x16 frames an image has side by side, read from its PNG header
+fn frame_count(path: &Path) -> Result<u32, String> {
+    let mut header = [0u8; 24];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(|error| format!("cannot read image '{}': {}", path.display(), error))?;
+    // The signature, then the IHDR chunk starting with the width
+    if &header[..8] != b"\x89PNG\r\n\x1a\n" || &header[12..16] != b"IHDR" {
+        return Err(format!("image '{}' is not a PNG", path.display()));
+    }
+    let width = u32::from_be_bytes([header[16], header[17], header[18], header[19]]);
+    Ok(width / TILE_SIZE as u32)
+}
+
+fn to_animated_tile(animation: &Animation) -> AnimatedTile {
+    AnimatedTile { start: animation.start, end: animation.end, speed: animation.speed }
+}
+
+// The name used for a TileType in the atlas files
+pub fn tile_type_name(tile_type: &TileType) -> &'static str {
+    match tile_type {
+        TileType::DeepWater => "DeepWater",
+        TileType::ShallowWater => "ShallowWater",
+        TileType::Sand => "Sand",
+        TileType::Grass => "Grass",
+        TileType::Street => "Street",
+        TileType::Hill => "Hill",
+        TileType::Mountain => "Mountain",
+        TileType::Snow => "Snow",
+        TileType::Lava => "Lava",
+        TileType::Teleport(_) => "Teleport",
+        TileType::Wall => "Wall",
+        _ => "Unknown",
+    }
+}
+
+// The name used for a Content in the atlas files, the quantity doesn't matter
+pub fn content_name(content: &Content) -> &'static str {
+    match content {
+        Content::Rock(_) => "Rock",
+        Content::Tree(_) => "Tree",
+        Content::Garbage(_) => "Garbage",
+        Content::Fire => "Fire",
+        Content::Coin(_) => "Coin",
+        Content::Bin(_) => "Bin",
+        Content::Crate(_) => "Crate",
+        Content::Bank(_) => "Bank",
+        Content::Water(_) => "Water",
+        Content::Market(_) => "Market",
+        Content::Fish(_) => "Fish",
+        Content::Building => "Building",
+        Content::Bush(_) => "Bush",
+        Content::JollyBlock(_) => "JollyBlock",
+        Content::Scarecrow => "Scarecrow",
+        _ => "None",
+    }
+}
+
+// The themes folder, under the same root the AssetServer loads the images from
+// (BEVY_ASSET_ROOT, then CARGO_MANIFEST_DIR, then the folder of the executable),
+// so starting the game from another folder still finds both
+pub fn themes_dir() -> PathBuf {
+    FileAssetIo::get_base_path().join(THEMES_DIR)
+}
+
+// Every theme found in THEMES_DIR, sorted by name, and the one in use
+#[derive(Resource)]
+pub struct Themes {
+    pub atlases: Vec<Atlas>,
+    pub current: usize,
+}
+
+impl Themes {
+    // Reads every descriptor in the folder, `initial` is the name of the theme to start with.
+    // The images are in the assets folder, the parent of the themes one
+    pub fn load(dir: &Path, initial: &str) -> Result<Themes, String> {
+        let assets = dir.parent().unwrap_or(dir);
+        let entries = fs::read_dir(dir)
+            .map_err(|error| format!("cannot read the themes folder '{}': {}", dir.display(), error))?;
+        let mut atlases = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|error| error.to_string())?.path();
+            if path.extension().map_or(true, |extension| extension != "ron") {
+                continue;
+            }
+            let text = fs::read_to_string(&path)
+                .map_err(|error| format!("cannot read theme '{}': {}", path.display(), error))?;
+            let atlas: Atlas = ron::from_str(&text)
+                .map_err(|error| format!("error in theme '{}' at {}", path.display(), error))?;
+            atlas.validate().map_err(|message| format!("theme '{}': {}", path.display(), message))?;
+            let tile_frames = frame_count(&assets.join(&atlas.tiles))?;
+            let content_frames = frame_count(&assets.join(&atlas.contents))?;
+            atlas
+                .check_frames(tile_frames, content_frames)
+                .map_err(|message| format!("theme '{}': {}", path.display(), message))?;
+            atlases.push(atlas);
+        }
+        atlases.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let current = atlases
+            .iter()
+            .position(|atlas| atlas.name.eq_ignore_ascii_case(initial))
+            .ok_or_else(|| format!("unknown theme '{}', the themes are in '{}'", initial, dir.display()))?;
+        Ok(Themes { atlases, current })
+    }
+
+    pub fn current(&self) -> &Atlas {
+        &self.atlases[self.current]
+    }
+
+    // Used by the theme buttons to cycle through the themes
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.atlases.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classic() -> Atlas {
+        let text = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(THEMES_DIR).join("classic.ron")).unwrap();
+        ron::from_str(&text).unwrap()
+    }
+
+    #[test]
+    fn shipped_themes_load() {
+        let themes = Themes::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join(THEMES_DIR), "classic").unwrap();
+        assert_eq!(themes.current().name, "classic");
+    }
+
+    #[test]
+    fn frame_past_the_image() {
+        let mut atlas = classic();
+        assert!(atlas.check_frames(29, 21).is_ok());
+
+        atlas.tile_types.insert("Grass".to_string(), 29);
+        let message = atlas.check_frames(29, 21).unwrap_err();
+        assert!(message.contains("tile_types.Grass"));
+    }
+
+    #[test]
+    fn animation_past_the_image() {
+        let atlas = classic();
+        // The Fire animation ends at frame 20, the Teleport one at 28
+        let message = atlas.check_frames(29, 20).unwrap_err();
+        assert!(message.contains("content_animations.Fire.end"));
+        let message = atlas.check_frames(28, 21).unwrap_err();
+        assert!(message.contains("tile_animations.Teleport.end"));
+    }
+}
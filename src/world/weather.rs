@@ -0,0 +1,261 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+use rand::Rng;
+use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
+
+use crate::camera::components::CameraMaker;
+use crate::camera::resources::CameraTarget;
+use crate::player::components::Player;
+use crate::world::components::{FogOverlay, NightOverlay, WeatherParticle};
+use crate::world::resources::{WeatherEffects, WorldRes};
+
+// Seconds for an effect to get most of the way to its new intensity
+const TRANSITION_SECONDS: f32 = 2.0;
+// Above the map and the robots, the night tints the fog and the rain too
+const PARTICLE_Z: f32 = 3.0;
+const FOG_Z: f32 = 4.0;
+const NIGHT_Z: f32 = 5.0;
+const MAX_PARTICLES: usize = 800;
+// Side of the fog texture, it's stretched over the screen
+const FOG_TEXTURE_SIZE: u32 = 128;
+// Screen pixels per second
+const RAIN_VELOCITY: Vec2 = Vec2::new(-60.0, -500.0);
+const MONSOON_VELOCITY: Vec2 = Vec2::new(-250.0, -700.0);
+const SNOW_VELOCITY: Vec2 = Vec2::new(-20.0, -60.0);
+
+pub fn spawn_weather_overlays(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+) {
+    commands.spawn(
+        (
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::ONE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, NIGHT_Z),
+                ..default()
+            },
+            NightOverlay{},
+        )
+    );
+
+    commands.spawn(
+        (
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::ONE),
+                    ..default()
+                },
+                texture: images.add(fog_texture()),
+                transform: Transform::from_xyz(0.0, 0.0, FOG_Z),
+                ..default()
+            },
+            FogOverlay{},
+        )
+    );
+}
+
+pub fn despawn_weather_overlays(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<NightOverlay>, With<FogOverlay>, With<WeatherParticle>)>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Clear in the middle and thicker towards the edges, the middle is put on the followed robot
+fn fog_texture() -> Image {
+    let size = FOG_TEXTURE_SIZE;
+    let mut image = Image::new_fill(
+        Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[255, 255, 255, 0],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    let center = (size as f32 - 1.0) / 2.0;
+    for x in 0..size {
+        for y in 0..size {
+            // 0 in the middle, 1 at a quarter of the texture from it and beyond
+            let distance = Vec2::new(x as f32 - center, y as f32 - center).length() / (size as f32 / 4.0);
+            let alpha = distance.clamp(0.0, 1.0).powf(1.5);
+            let index = ((y * size + x) * 4 + 3) as usize;
+            image.data[index] = (alpha * 255.0) as u8;
+        }
+    }
+    image
+}
+
+// How dark it is, from 0 during the day to 1 at night, from the "hh:mm" of the conditions
+pub fn night_amount(conditions: &EnvironmentalConditions) -> f32 {
+    let time = conditions.get_time_of_day_string();
+    let mut parts = time.split(':').map(|part| part.trim().parse::<f32>().ok());
+    let hour = match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(hour), Some(minute)) => hour + minute / 60.0,
+        (Some(hour), None) => hour,
+        // Unknown format, better a day than a black screen
+        _ => 12.0,
+    };
+    // Dawn from 5 to 8, dusk from 18 to 21
+    if hour < 5.0 || hour >= 21.0 {
+        1.0
+    } else if hour < 8.0 {
+        1.0 - (hour - 5.0) / 3.0
+    } else if hour < 18.0 {
+        0.0
+    } else {
+        (hour - 18.0) / 3.0
+    }
+}
+
+// Moves every effect towards what the current conditions ask for
+pub fn update_weather_effects(
+    world: Res<WorldRes>,
+    time: Res<Time>,
+    mut effects: ResMut<WeatherEffects>,
+) {
+    let conditions = &world.environmental_conditions;
+    let weather = conditions.get_weather_condition();
+    let target = WeatherEffects {
+        night: night_amount(conditions),
+        rain: match weather {
+            WeatherType::Rainy => 0.5,
+            WeatherType::TropicalMonsoon => 1.0,
+            _ => 0.0,
+        },
+        wind: if matches!(weather, WeatherType::TropicalMonsoon) { 1.0 } else { 0.0 },
+        snow: if matches!(weather, WeatherType::TrentinoSnow) { 1.0 } else { 0.0 },
+        fog: if matches!(weather, WeatherType::Foggy) { 1.0 } else { 0.0 },
+    };
+
+    let step = 1.0 - (-time.delta_seconds() / TRANSITION_SECONDS).exp();
+    let approach = |current: &mut f32, target: f32| *current += (target - *current) * step;
+    approach(&mut effects.night, target.night);
+    approach(&mut effects.rain, target.rain);
+    approach(&mut effects.wind, target.wind);
+    approach(&mut effects.snow, target.snow);
+    approach(&mut effects.fog, target.fog);
+}
+
+// The night covers the whole screen, the fog is centered on the followed robot
+pub fn update_weather_overlays(
+    effects: Res<WeatherEffects>,
+    camera_target: Res<CameraTarget>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<CameraMaker>>,
+    robot_query: Query<(&Transform, &Player), (Without<CameraMaker>, Without<NightOverlay>, Without<FogOverlay>)>,
+    mut night_query: Query<(&mut Transform, &mut Sprite), (With<NightOverlay>, Without<FogOverlay>, Without<CameraMaker>)>,
+    mut fog_query: Query<(&mut Transform, &mut Sprite), (With<FogOverlay>, Without<NightOverlay>, Without<CameraMaker>)>,
+) {
+    let (Ok(window), Ok((camera, projection))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let view = Vec2::new(window.width(), window.height()) * projection.scale;
+
+    if let Ok((mut transform, mut sprite)) = night_query.get_single_mut() {
+        transform.translation.x = camera.translation.x;
+        transform.translation.y = camera.translation.y;
+        sprite.custom_size = Some(view * 1.1);
+        // Orange at dawn and dusk, dark blue at night
+        let twilight = effects.night * (1.0 - effects.night) * 4.0;
+        let dusk = Color::rgb(0.9, 0.45, 0.1);
+        let night = Color::rgb(0.02, 0.02, 0.15);
+        let t = effects.night;
+        sprite.color = Color::rgba(
+            dusk.r() + (night.r() - dusk.r()) * t,
+            dusk.g() + (night.g() - dusk.g()) * t,
+            dusk.b() + (night.b() - dusk.b()) * t,
+            0.55 * effects.night + 0.1 * twilight,
+        );
+    }
+
+    if let Ok((mut transform, mut sprite)) = fog_query.get_single_mut() {
+        let center = robot_query
+            .iter()
+            .find(|(_, player)| player.id == camera_target.robot)
+            .map(|(transform, _)| transform.translation.truncate())
+            .unwrap_or(camera.translation.truncate());
+        transform.translation.x = center.x;
+        transform.translation.y = center.y;
+        // Big enough to cover the screen wherever the robot is on it
+        sprite.custom_size = Some(Vec2::splat(view.max_element() * 3.0));
+        sprite.color = Color::rgba(0.85, 0.85, 0.9, 0.9 * effects.fog);
+    }
+}
+
+// Rain and snow fall from the top of the screen and disappear at the bottom
+pub fn update_weather_particles(
+    mut commands: Commands,
+    effects: Res<WeatherEffects>,
+    time: Res<Time>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<CameraMaker>>,
+    mut particles: Query<(Entity, &mut Transform, &WeatherParticle), Without<CameraMaker>>,
+    // Particles owed from the previous frames, they are spawned whole
+    mut to_spawn: Local<f32>,
+) {
+    let (Ok(window), Ok((camera, projection))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let scale = projection.scale;
+    let half_view = Vec2::new(window.width(), window.height()) * scale / 2.0;
+    // A minimized window has no size, and gen_range panics on the empty spawn range
+    if half_view.x <= 0.0 || half_view.y <= 0.0 {
+        return;
+    }
+    let center = camera.translation.truncate();
+    let delta = time.delta_seconds();
+
+    let mut count = 0;
+    for (entity, mut transform, particle) in particles.iter_mut() {
+        transform.translation += (particle.velocity * scale * delta).extend(0.0);
+        // Also the ones left behind when the camera moves
+        let offset = transform.translation.truncate() - center;
+        if offset.y < -half_view.y || offset.x.abs() > half_view.x * 1.5 || offset.y > half_view.y * 1.5 {
+            commands.entity(entity).despawn();
+        } else {
+            count += 1;
+        }
+    }
+
+    // Particles per second at full intensity
+    *to_spawn += (effects.rain * 300.0 + effects.snow * 120.0) * delta;
+    let mut rng = rand::thread_rng();
+    while *to_spawn >= 1.0 {
+        *to_spawn -= 1.0;
+        if count >= MAX_PARTICLES {
+            continue;
+        }
+        count += 1;
+        let snow = rng.gen::<f32>() * (effects.rain + effects.snow) < effects.snow;
+        let (velocity, size, color) = if snow {
+            (SNOW_VELOCITY + Vec2::new(rng.gen_range(-15.0..15.0), 0.0), Vec2::splat(2.0), Color::rgba(1.0, 1.0, 1.0, 0.9))
+        } else {
+            let velocity = RAIN_VELOCITY + (MONSOON_VELOCITY - RAIN_VELOCITY) * effects.wind;
+            (velocity, Vec2::new(1.0, 8.0), Color::rgba(0.6, 0.7, 1.0, 0.6))
+        };
+        // Slanted rain comes in from the right too
+        let x = center.x + rng.gen_range(-half_view.x..half_view.x * 1.4);
+        let y = center.y + half_view.y + rng.gen_range(0.0..half_view.y * 0.2);
+        commands.spawn(
+            (
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(size * scale),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(x, y, PARTICLE_Z)
+                        .with_rotation(Quat::from_rotation_z(velocity.x.atan2(-velocity.y))),
+                    ..default()
+                },
+                WeatherParticle { velocity },
+            )
+        );
+    }
+}